@@ -0,0 +1,14 @@
+extern crate colored;
+extern crate dirs;
+extern crate fs_extra;
+extern crate git2;
+extern crate rustyline;
+extern crate toml;
+
+#[macro_use]
+pub mod wu;
+
+pub use self::wu::*;
+
+pub use self::wu::driver::{check_source, compile_source, CompileOptions, CompiledModule};
+pub use self::wu::error::Diagnostic;
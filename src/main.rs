@@ -1,23 +1,20 @@
 #![deny(mutable_borrow_reservation_conflict)]
 extern crate colored;
 extern crate dirs;
-extern crate fs_extra;
-extern crate git2;
-extern crate rustyline;
-extern crate toml;
+#[macro_use]
+extern crate wu;
 
 use self::colored::Colorize;
 
-mod wu;
-
-use self::wu::compiler::*;
-use self::wu::error::*;
-use self::wu::handler;
-use self::wu::lexer::*;
-use self::wu::parser::*;
-use self::wu::source::*;
-use self::wu::visitor::*;
+use wu::error::*;
+use wu::handler;
+use wu::repl;
+use wu::bundle::bundle;
+use wu::compiler::trace;
+use wu::driver::imports;
+use wu::{check_source, compile_source, CompileOptions, CompiledModule};
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::fs::metadata;
 use std::fs::File;
@@ -25,8 +22,10 @@ use std::fs::File;
 use std::env;
 
 use std::io::prelude::*;
-use std::path::Path;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 const HELP: &'static str = "\
 The Wu Compiler
@@ -36,27 +35,98 @@ Usage:
     wu <file>         # Compile .wu file to corresponding .lua file
     wu <folder>       # Compile all .wu files in given folder
     wu clean <folder> # Removes all compiled .lua files from given folder
+    wu check [path]   # Type-check file or folder without writing any .lua
+    wu watch [path]   # Recompile changed files, and whatever imports them, as they change
+    wu bundle [file]  # Bundle file (or `[package] entry`) and its imports into one .lua, `-o <out>`
+    wu trace <file>   # Point Lua file:line references in a traceback back at .wu sources
+    wu repl [--lua]   # Explore types interactively, optionally showing the Lua
 
 Project usage:
-    wu new <name>     # Create a new Wu project
-    wu sync           # Installs/synchronizes dependencies
-    wu build          # Installs dependencies and builds current project
+    wu new <name>     # Create a new Wu project, `--template <lib|app|love2d>` or one in `$WU_HOME/templates`
+    wu sync           # Installs/synchronizes dependencies at their locked commits
+    wu update [name]  # Moves locked dependencies forward to their latest commits
+    wu add <user/repo> # Adds and installs a dependency, also `--name <name>` and `--tag <tag>`
+    wu remove <name>  # Removes a dependency and its checkout
+    wu cache list     # Shows the remotes and commits cached in `$WU_HOME`, shared by all projects
+    wu cache clean [url] # Empties the cache, or just the remotes matching <url>
+    wu build          # Installs dependencies and builds current project, or every workspace member
+    wu vendor         # Copies resolved dependencies into `vendor/`, for building offline
+
+Options:
+    --message-format=json  # Print diagnostics as JSON, one object per line
+    --out-dir <dir>        # Mirror compiled .lua files into <dir>, also `[build] out_dir`
+    --offline              # Install dependencies only from the libpath, `vendor/` or the cache
 ";
 
-fn compile_path(path: &str, root: &String) {
+#[derive(Clone, Copy, PartialEq)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    fn emitter(&self) -> Box<dyn Emitter> {
+        match *self {
+            MessageFormat::Human => Box::new(Terminal),
+            MessageFormat::Json => Box::new(Json),
+        }
+    }
+
+    fn emit(&self, diagnostic: &Diagnostic) {
+        self.emitter().emit(diagnostic)
+    }
+
+    // progress lines stay out of the way of JSON on stdout
+    fn status(&self, message: String) {
+        self.emitter().status(&message)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Build,
+    Check,
+}
+
+// What the command line and wu.toml asked for
+#[derive(Clone)]
+struct Settings {
+    format: MessageFormat,
+    out_dir: Option<String>,
+    // dependencies come from disk only
+    offline: bool,
+    // canonical directories compiled some other way, like a workspace's members
+    skip: Vec<String>,
+}
+
+// Returns whether everything under `path` went through without errors
+fn compile_path(path: &str, root: &String, settings: &Settings, mode: Mode) -> bool {
+    // it may have gone away since it was found, like while watching
     let meta = match metadata(path) {
         Ok(m) => m,
-        Err(why) => panic!("{}", why),
+        Err(why) => {
+            settings.format.emit(&response!(Response::Wrong(format!(
+                "failed to read `{}`: {}",
+                path, why
+            ))));
+
+            return false;
+        }
     };
 
     if meta.is_file() {
         let split: Vec<&str> = path.split('.').collect();
 
-        println!(
+        let action = match mode {
+            Mode::Build => "Compiling",
+            Mode::Check => " Checking",
+        };
+
+        settings.format.status(format!(
             "{} {}",
-            "Compiling".green().bold(),
+            action.green().bold(),
             path.to_string().replace("./", "")
-        );
+        ));
 
         if *split.last().unwrap() == "wu" {
             let meta = match metadata(root) {
@@ -72,134 +142,227 @@ fn compile_path(path: &str, root: &String) {
                 }
             }
 
-            if let Some(n) = file_content(path, &root) {
-                write(path, &n);
+            match file_content(path, &root, settings, mode) {
+                Ok(Some(module)) => {
+                    if !write(path, &module, settings) {
+                        return false;
+                    }
+                }
+                Ok(None) => (),
+                Err(()) => return false,
             }
         }
+
+        true
     } else {
-        let paths = fs::read_dir(path).unwrap();
+        let paths = match fs::read_dir(path) {
+            Ok(paths) => paths,
+            Err(why) => {
+                settings.format.emit(&response!(Response::Wrong(format!(
+                    "failed to read `{}`: {}",
+                    path, why
+                ))));
+
+                return false;
+            }
+        };
+
+        let mut success = true;
 
-        for folder_path in paths {
-            let folder_path = format!("{}", folder_path.unwrap().path().display());
+        for folder_path in paths.flatten() {
+            let folder_path = format!("{}", folder_path.path().display());
             let split: Vec<&str> = folder_path.split('.').collect();
 
             if Path::new(&folder_path).is_dir() || *split.last().unwrap() == "wu" {
-                compile_path(&folder_path, root)
+                if is_skipped(&folder_path, settings) {
+                    continue;
+                }
+
+                success &= compile_path(&folder_path, root, settings, mode)
             }
         }
+
+        success
     }
 }
 
-fn file_content(path: &str, root: &String) -> Option<String> {
+fn file_content(
+    path: &str,
+    root: &String,
+    settings: &Settings,
+    mode: Mode,
+) -> Result<Option<CompiledModule>, ()> {
     let display = Path::new(path).display();
 
     let mut file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
+        Err(why) => {
+            settings.format.emit(&response!(Response::Wrong(format!(
+                "failed to open `{}`: {}",
+                display, why
+            ))));
+
+            return Err(());
+        }
         Ok(file) => file,
     };
 
     let mut s = String::new();
 
     match file.read_to_string(&mut s) {
-        Err(why) => panic!("failed to read {}: {}", display, why),
-        Ok(_) => run(&s, path, root),
+        Err(why) => {
+            settings.format.emit(&response!(Response::Wrong(format!(
+                "failed to read `{}`: {}",
+                display, why
+            ))));
+
+            Err(())
+        }
+        Ok(_) => run(&s, path, root, settings, mode),
     }
 }
 
-fn write(path: &str, data: &str) {
-    let path = Path::new(path);
+// Where the `.lua` compiled from a `.wu` file lives
+fn lua_path(path: &str, out_dir: Option<&str>) -> PathBuf {
+    let path = Path::new(path.trim_start_matches("./")).with_extension("lua");
+
+    match out_dir {
+        Some(out_dir) => Path::new(out_dir).join(path),
+        None => path,
+    }
+}
 
-    let split_name = path.file_name().unwrap().to_str().unwrap().split('.');
-    let split: Vec<&str> = split_name.collect();
+fn is_out_dir(path: &str, out_dir: Option<&str>) -> bool {
+    match out_dir {
+        Some(out_dir) => Path::new(path.trim_start_matches("./")) == Path::new(out_dir),
+        None => false,
+    }
+}
 
-    let path_split = path.to_str().unwrap().split('/').collect::<Vec<&str>>();
+fn is_skipped(path: &str, settings: &Settings) -> bool {
+    // the vendored tree is committed as fetched, building doesn't write into it
+    if is_out_dir(path, settings.out_dir.as_deref())
+        || is_out_dir(path, Some(handler::VENDOR_DIR))
+    {
+        return true;
+    }
 
-    let path_real = if path_split.len() > 1 {
-        format!(
-            "{}/{}.lua",
-            path_split[0..path_split.len() - 1].join("/"),
-            split[0]
-        )
-    } else {
-        format!("{}.lua", split[0])
-    };
+    if settings.skip.is_empty() {
+        return false;
+    }
 
-    let mut output_file = File::create(&path_real).unwrap();
-    match output_file.write_all(data.as_bytes()) {
-        Ok(_) => (),
-        Err(why) => println!("{}", why),
+    match fs::canonicalize(path) {
+        Ok(path) => settings.skip.iter().any(|skip| path == Path::new(skip)),
+        Err(_) => false,
     }
 }
 
-pub fn run(content: &str, file: &str, root: &String) -> Option<String> {
-    let source = Source::from(
-        file,
-        content.lines().map(|x| x.into()).collect::<Vec<String>>(),
-    );
-    let lexer = Lexer::default(content.chars().collect(), &source);
+// Writes the Lua along with its `.lua.map`, reporting whatever can't be written
+fn write(path: &str, module: &CompiledModule, settings: &Settings) -> bool {
+    let path_real = lua_path(path, settings.out_dir.as_deref());
+    let map_path = PathBuf::from(format!("{}.map", path_real.display()));
 
-    let mut tokens = Vec::new();
+    let failed = |path: &Path, why: std::io::Error| {
+        settings.format.emit(&response!(Response::Wrong(format!(
+            "failed to write `{}`: {}",
+            path.display(),
+            why
+        ))));
 
-    for token_result in lexer {
-        if let Ok(token) = token_result {
-            tokens.push(token)
-        } else {
-            return None;
+        false
+    };
+
+    if let Some(parent) = path_real.parent() {
+        if !parent.as_os_str().is_empty() {
+            if let Err(why) = fs::create_dir_all(parent) {
+                return failed(parent, why);
+            }
         }
     }
 
-    let mut parser = Parser::new(tokens, &source);
+    if let Err(why) = fs::write(&path_real, &module.lua) {
+        return failed(&path_real, why);
+    }
 
-    match parser.parse() {
-        Ok(ref ast) => {
-            let mut symtab = SymTab::new();
+    if let Err(why) = fs::write(&map_path, module.map.to_string()) {
+        return failed(&map_path, why);
+    }
 
-            let splat_any = Type::new(TypeNode::Any, TypeMode::Splat(None));
+    true
+}
 
-            symtab.assign_str(
-                "print",
-                Type::function(vec![splat_any.clone()], Type::from(TypeNode::Nil), false),
-            );
+fn run(
+    content: &str,
+    file: &str,
+    root: &String,
+    settings: &Settings,
+    mode: Mode,
+) -> Result<Option<CompiledModule>, ()> {
+    let options = CompileOptions {
+        root: root.clone(),
+        out_dir: settings.out_dir.clone(),
+    };
 
-            symtab.assign_str(
-                "ipairs",
-                Type::function(vec![splat_any.clone()], splat_any.clone(), false),
-            );
+    let result = match mode {
+        Mode::Build => compile_source(file, content, &options),
+        Mode::Check => check_source(file, content, &options).map(|warnings| CompiledModule {
+            warnings,
+            ..Default::default()
+        }),
+    };
 
-            symtab.assign_str(
-                "pairs",
-                Type::function(vec![splat_any.clone()], splat_any, false),
-            );
+    match result {
+        Ok(module) => {
+            for warning in module.warnings.iter() {
+                settings.format.emit(warning)
+            }
 
-            let mut visitor = Visitor::from_symtab(ast, &source, symtab, root.clone());
+            // checking leaves nothing to write
+            Ok(Some(module).filter(|_| mode == Mode::Build))
+        }
 
-            match visitor.visit() {
-                Ok(_) => (),
-                _ => return None,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics.iter() {
+                settings.format.emit(diagnostic)
             }
 
-            let mut generator = Generator::new(&source, &visitor.method_calls, &visitor.import_map);
-
-            Some(generator.generate(&ast))
+            Err(())
         }
-
-        _ => None,
     }
 }
 
-fn clean_path(path: &str) {
+// Returns whether everything under `path` could be looked at
+fn clean_path(path: &str, out_dir: Option<&str>) -> bool {
     let meta = match metadata(path) {
         Ok(m) => m,
-        Err(why) => panic!("{}", why),
+        Err(why) => {
+            Terminal.emit(&response!(Response::Wrong(format!(
+                "failed to read `{}`: {}",
+                path, why
+            ))));
+
+            return false;
+        }
     };
 
     if meta.is_dir() {
-        let paths = fs::read_dir(path).unwrap();
+        let paths = match fs::read_dir(path) {
+            Ok(paths) => paths,
+            Err(why) => {
+                Terminal.emit(&response!(Response::Wrong(format!(
+                    "failed to read `{}`: {}",
+                    path, why
+                ))));
+
+                return false;
+            }
+        };
+
+        let mut success = true;
 
-        for path in paths {
-            let path = path.unwrap().path();
+        for path in paths.flatten() {
+            let path = path.path();
             if path.is_dir() {
-                clean_path(&path.display().to_string())
+                success &= clean_path(&path.display().to_string(), out_dir)
             } else {
                 let path = format!("{}", path.display());
                 let split: Vec<&str> = path.split('.').collect();
@@ -207,7 +370,7 @@ fn clean_path(path: &str) {
                 // removes lua file if wu source exists
                 match split.last() {
                     Some(n) if *n == "wu" => {
-                        let path = format!("{}.lua", split[0..split.len() - 1].to_vec().join("."));
+                        let path = lua_path(&path, out_dir).display().to_string();
 
                         if Path::new(&path).is_file() {
                             println!("{} {}", "Removing".red().bold(), path.replace("./", ""));
@@ -217,15 +380,17 @@ fn clean_path(path: &str) {
                                 Err(why) => panic!("{}", why),
                             }
                         }
+
+                        remove_map(&path)
                     }
                     _ => continue,
                 }
             }
         }
-    } else {
-        let split: Vec<&str> = path.split('.').collect();
 
-        let path = format!("{}.lua", split[0..split.len() - 1].to_vec().join("."));
+        success
+    } else {
+        let path = lua_path(path, out_dir).display().to_string();
 
         if Path::new(&path).is_file() {
             match fs::remove_file(&path) {
@@ -233,6 +398,152 @@ fn clean_path(path: &str) {
                 Err(why) => panic!("{}", why),
             }
         }
+
+        remove_map(&path);
+
+        true
+    }
+}
+
+fn remove_map(lua_path: &str) {
+    let path = format!("{}.map", lua_path);
+
+    if Path::new(&path).is_file() {
+        match fs::remove_file(&path) {
+            Ok(_) => (),
+            Err(why) => panic!("{}", why),
+        }
+    }
+}
+
+// Removes the directories that cleaning left empty in the output directory
+fn prune_dir(path: &Path) {
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries {
+            let entry = entry.unwrap().path();
+
+            if entry.is_dir() {
+                prune_dir(&entry)
+            }
+        }
+    }
+
+    // only succeeds when empty
+    let _ = fs::remove_dir(path);
+}
+
+// Every .wu file under `path` along with when it was last modified
+fn sources(path: &Path, out_dir: Option<&str>, found: &mut HashMap<PathBuf, SystemTime>) {
+    if path.is_dir() {
+        let display = path.display().to_string();
+
+        if is_out_dir(&display, out_dir) || is_out_dir(&display, Some(handler::VENDOR_DIR)) {
+            return;
+        }
+
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                sources(&entry.path(), out_dir, found)
+            }
+        }
+    } else if path.extension().is_some_and(|extension| extension == "wu") {
+        if let Ok(modified) = metadata(path).and_then(|meta| meta.modified()) {
+            found.insert(path.to_path_buf(), modified);
+        }
+    }
+}
+
+// The files `path` imports, as far as they can be found next to it
+fn imported_files(path: &Path) -> Vec<PathBuf> {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let folder = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut files = Vec::new();
+
+    for name in imports(&path.display().to_string(), &content) {
+        let file = folder.join(format!("{}.wu", name));
+
+        if file.is_file() {
+            files.push(file)
+        } else {
+            files.push(folder.join(&name).join("init.wu"))
+        }
+    }
+
+    files
+}
+
+fn watch(path: &str, settings: &Settings) {
+    let mut known = HashMap::new();
+    let mut importers: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
+
+    settings.format.status(format!(
+        "{} {}",
+        "Watching".green().bold(),
+        path.replace("./", "")
+    ));
+
+    loop {
+        let mut found = HashMap::new();
+
+        sources(Path::new(path), settings.out_dir.as_deref(), &mut found);
+
+        let mut changed = found
+            .iter()
+            .filter(|(file, modified)| known.get(*file) != Some(*modified))
+            .map(|(file, _)| file.clone())
+            .collect::<Vec<PathBuf>>();
+
+        // removed files count as changes too, so whatever imported them hears about it
+        changed.extend(known.keys().filter(|file| !found.contains_key(*file)).cloned());
+
+        if !changed.is_empty() {
+            let now = Instant::now();
+
+            for file in changed.iter() {
+                for files in importers.values_mut() {
+                    files.remove(file);
+                }
+
+                for imported in imported_files(file) {
+                    importers.entry(imported).or_default().insert(file.clone());
+                }
+            }
+
+            // changes ripple up through everything importing them
+            let mut dirty = changed.clone();
+            let mut seen = changed.iter().cloned().collect::<HashSet<PathBuf>>();
+
+            let mut i = 0;
+
+            while i < dirty.len() {
+                if let Some(files) = importers.get(&dirty[i]) {
+                    for file in files {
+                        if found.contains_key(file) && seen.insert(file.clone()) {
+                            dirty.push(file.clone())
+                        }
+                    }
+                }
+
+                i += 1
+            }
+
+            for file in dirty.iter().filter(|file| found.contains_key(*file)) {
+                let file = file.display().to_string();
+
+                compile_path(&file, &path.to_string(), settings, Mode::Build);
+            }
+
+            settings.format.status(format!(
+                "{} things in {}ms",
+                "  Finished".green().bold(),
+                now.elapsed().as_millis()
+            ));
+        }
+
+        known = found;
+
+        thread::sleep(Duration::from_millis(250))
     }
 }
 
@@ -241,19 +552,17 @@ fn confirm_home() {
         let dir = if let Some(dir) = dirs::home_dir() {
             format!("{}/.wu/libs/", dir.display())
         } else {
-            return response!(
+            return Terminal.emit(&response!(
                 Response::Weird(format!("missing environment variable `WU_HOME`")),
                 Response::Note("failed to find home directory, you can set the variable yourself")
-            );
+            ));
         };
 
-        if !Path::new(&dir).exists() {
-            if fs::create_dir_all(dir).is_err() {
-                response!(
-                    Response::Weird(format!("missing environment variable `WU_HOME`")),
-                    Response::Note("run again as super-user to fix this automatically")
-                )
-            }
+        if !Path::new(&dir).exists() && fs::create_dir_all(&dir).is_err() {
+            Terminal.emit(&response!(
+                Response::Weird(format!("missing environment variable `WU_HOME`")),
+                Response::Note("run again as super-user to fix this automatically")
+            ))
         } else {
             env::set_var("WU_HOME", dir)
         }
@@ -263,7 +572,49 @@ fn confirm_home() {
 fn main() {
     confirm_home();
 
-    let args = env::args().collect::<Vec<String>>();
+    let mut format = MessageFormat::Human;
+    let mut out_dir = None;
+    let mut offline = false;
+
+    let mut args = Vec::new();
+
+    let mut raw_args = env::args();
+
+    while let Some(arg) = raw_args.next() {
+        if arg == "--out-dir" {
+            out_dir = raw_args.next()
+        } else if let Some(value) = arg.strip_prefix("--out-dir=") {
+            out_dir = Some(value.to_string())
+        } else if arg == "--offline" {
+            offline = true
+        } else if let Some(value) = arg.strip_prefix("--message-format=") {
+            format = match value {
+                "human" => MessageFormat::Human,
+                "json" => MessageFormat::Json,
+
+                _ => {
+                    return Terminal.emit(&response!(
+                        Response::Wrong(format!("unknown message format `{}`", value)),
+                        Response::Note("expected `human` or `json`")
+                    ))
+                }
+            }
+        } else {
+            args.push(arg)
+        }
+    }
+
+    let out_dir = out_dir
+        .or_else(handler::out_dir)
+        .map(|dir| dir.trim_start_matches("./").trim_end_matches('/').to_string())
+        .filter(|dir| !dir.is_empty() && dir != ".");
+
+    let settings = Settings {
+        format,
+        out_dir,
+        offline,
+        skip: Vec::new(),
+    };
 
     let root = Path::new(&args[0].to_string())
         .parent()
@@ -275,40 +626,259 @@ fn main() {
         match args[1].as_str() {
             "clean" => {
                 if args.len() > 2 {
-                    clean_path(&args[2])
+                    let success = clean_path(&args[2], settings.out_dir.as_deref());
+
+                    if let Some(ref out_dir) = settings.out_dir {
+                        prune_dir(Path::new(out_dir))
+                    }
+
+                    if !success {
+                        process::exit(1)
+                    }
                 }
             }
 
             "new" => {
-                if args.len() > 2 {
-                    handler::new(Some(&args[2]))
+                let template = args.iter().position(|arg| arg == "--template");
+
+                // whatever isn't `--template` or its value names the project
+                let name = args
+                    .iter()
+                    .enumerate()
+                    .skip(2)
+                    .find(|(i, _)| template.is_none_or(|t| *i != t && *i != t + 1))
+                    .map(|(_, arg)| arg.as_str());
+
+                handler::new(
+                    name,
+                    template.and_then(|t| args.get(t + 1)).map(|t| t.as_str()),
+                    &mut *settings.format.emitter(),
+                )
+            }
+
+            "build" => {
+                // offline there's no trying again later, so a missing dependency stops the build
+                let synced = handler::get(settings.offline, &mut *settings.format.emitter());
+
+                if !synced && settings.offline {
+                    process::exit(1)
+                }
+
+                let success = if args.len() > 2 {
+                    compile_path(&args[2], &root, &settings, Mode::Build)
+                } else if let Some((targets, skip)) = handler::workspace_build() {
+                    let settings = Settings {
+                        skip,
+                        ..settings.clone()
+                    };
+
+                    let mut success = true;
+
+                    for target in targets.iter() {
+                        success &= compile_path(target, &root, &settings, Mode::Build)
+                    }
+
+                    success
                 } else {
-                    handler::new(None)
+                    compile_path(".", &root, &settings, Mode::Build)
+                };
+
+                if !success {
+                    process::exit(1)
                 }
             }
 
-            "build" => {
-                handler::get();
+            "check" => {
+                let now = Instant::now();
+
+                let path = if args.len() > 2 { args[2].as_str() } else { "." };
+
+                let success = compile_path(path, &path.to_string(), &settings, Mode::Check);
+
+                settings.format.status(format!(
+                    "{} checking in {}ms",
+                    "  Finished".green().bold(),
+                    now.elapsed().as_millis()
+                ));
+
+                if !success {
+                    process::exit(1)
+                }
+            }
+
+            "sync" => {
+                if !handler::get(settings.offline, &mut *settings.format.emitter()) {
+                    process::exit(1)
+                }
+            }
 
+            "vendor" => {
+                if !handler::vendor(&mut *settings.format.emitter()) {
+                    process::exit(1)
+                }
+            }
+
+            "update" => {
+                if settings.offline {
+                    settings.format.emit(&response!(
+                        Response::Wrong("can't move dependencies forward offline"),
+                        Response::Note("`--offline` only installs what's already locked")
+                    ));
+
+                    process::exit(1)
+                }
+
+                let name = args.get(2).map(|name| name.as_str());
+
+                if !handler::update(name, &mut *settings.format.emitter()) {
+                    process::exit(1)
+                }
+            }
+
+            "add" => {
                 if args.len() > 2 {
-                    compile_path(&args[2], &root)
-                } else {
-                    compile_path(".", &root)
+                    let flag = |name: &str| match args.iter().position(|arg| arg == name) {
+                        Some(i) if i + 1 < args.len() => Some(args[i + 1].as_str()),
+                        _ => None,
+                    };
+
+                    let mut emitter = settings.format.emitter();
+
+                    if !handler::add(&args[2], flag("--name"), flag("--tag"), &mut *emitter) {
+                        process::exit(1)
+                    }
+                }
+            }
+
+            "cache" => {
+                let success = match args.get(2).map(|command| command.as_str()) {
+                    Some("list") => handler::cache_list(&mut *settings.format.emitter()),
+                    Some("clean") => handler::cache_clean(
+                        args.get(3).map(|filter| filter.as_str()),
+                        &mut *settings.format.emitter(),
+                    ),
+
+                    _ => {
+                        settings.format.emit(&response!(
+                            Response::Wrong("expected `wu cache list` or `wu cache clean [url]`")
+                        ));
+
+                        false
+                    }
+                };
+
+                if !success {
+                    process::exit(1)
+                }
+            }
+
+            "remove" => {
+                if args.len() > 2 && !handler::remove(&args[2], &mut *settings.format.emitter()) {
+                    process::exit(1)
+                }
+            }
+
+            "bundle" => {
+                // `-o` comes with a value, anything else first is the entry
+                let entry = match args.get(2) {
+                    Some(arg) if arg != "-o" => arg.clone(),
+                    _ => match handler::entry() {
+                        Some(entry) => entry,
+                        None => {
+                            settings.format.emit(&response!(
+                                Response::Wrong("no entry to bundle"),
+                                Response::Note("pass a file or set `[package] entry` in `wu.toml`")
+                            ));
+
+                            process::exit(1)
+                        }
+                    },
+                };
+
+                let now = Instant::now();
+
+                let output = match args.iter().position(|arg| arg == "-o") {
+                    Some(i) if i + 1 < args.len() => args[i + 1].clone(),
+                    _ => "bundle.lua".to_string(),
+                };
+
+                settings.format.status(format!(
+                    "{} {} => `{}`",
+                    "Bundling".green().bold(),
+                    entry.replace("./", ""),
+                    output
+                ));
+
+                let options = CompileOptions {
+                    root: entry.clone(),
+                    out_dir: None,
+                };
+
+                match bundle(&entry, &options) {
+                    Ok((lua, warnings)) => {
+                        for warning in warnings.iter() {
+                            settings.format.emit(warning)
+                        }
+
+                        if let Err(why) = fs::write(&output, lua) {
+                            settings.format.emit(&response!(Response::Wrong(format!(
+                                "failed to write `{}`: {}",
+                                output, why
+                            ))));
+
+                            process::exit(1)
+                        }
+                    }
+
+                    Err(diagnostics) => {
+                        for diagnostic in diagnostics.iter() {
+                            settings.format.emit(diagnostic)
+                        }
+
+                        process::exit(1)
+                    }
                 }
+
+                settings.format.status(format!(
+                    "{} things in {}ms",
+                    "  Finished".green().bold(),
+                    now.elapsed().as_millis()
+                ));
             }
 
-            "sync" => handler::get(),
+            "watch" => {
+                let path = if args.len() > 2 { args[2].as_str() } else { "." };
+
+                watch(path, &settings)
+            }
+
+            "trace" => {
+                if args.len() > 2 {
+                    match fs::read_to_string(&args[2]) {
+                        Ok(text) => print!("{}", trace(&text)),
+                        Err(why) => settings.format.emit(&response!(
+                            Response::Wrong(format!("failed to read `{}`: {}", args[2], why))
+                        )),
+                    }
+                }
+            }
+
+            "repl" => repl::repl(args.iter().any(|arg| arg == "--lua")),
 
             file => {
                 let now = Instant::now();
 
-                compile_path(&file, &file.to_string());
+                let success = compile_path(&file, &file.to_string(), &settings, Mode::Build);
 
-                println!(
+                settings.format.status(format!(
                     "{} things in {}ms",
                     "  Finished".green().bold(),
                     now.elapsed().as_millis()
-                );
+                ));
+
+                if !success {
+                    process::exit(1)
+                }
             }
         }
     } else {
@@ -0,0 +1,92 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use super::driver::{compile_source, CompileOptions, CompiledModule};
+use super::error::Diagnostic;
+use super::error::Response::Wrong;
+
+// Compiles `entry` and everything it imports into one standalone Lua chunk,
+// each module becoming a `package.preload` loader under the name it is `require`d by.
+// Hands back the warnings of every module along with the chunk
+pub fn bundle(
+    entry: &str,
+    options: &CompileOptions,
+) -> Result<(String, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+
+    let entry_module = compile_file(entry, options)?;
+
+    let mut warnings = entry_module.warnings.clone();
+
+    let mut pending = entry_module.requires.clone();
+
+    let mut defined = HashSet::new();
+    let mut loaded: HashMap<String, String> = HashMap::new();
+
+    let mut result = String::new();
+
+    let mut i = 0;
+
+    while i < pending.len() {
+        let (name, file) = pending[i].clone();
+        let file = file.trim_start_matches("./").to_string();
+
+        i += 1;
+
+        if !defined.insert(name.clone()) {
+            continue;
+        }
+
+        // the same file imported under another name
+        if let Some(first) = loaded.get(&file) {
+            result.push_str(&format!(
+                "package.preload['{}'] = package.preload['{}']\n\n",
+                name, first
+            ));
+
+            continue;
+        }
+
+        loaded.insert(file.clone(), name.clone());
+
+        match compile_file(&file, options) {
+            Ok(module) => {
+                pending.extend(module.requires.iter().cloned());
+                warnings.extend(module.warnings.iter().cloned());
+
+                result.push_str(&format!("package.preload['{}'] = function(...)\n", name));
+
+                for line in module.lua.lines() {
+                    result.push_str("  ");
+                    result.push_str(line);
+                    result.push('\n')
+                }
+
+                result.push_str("end\n\n")
+            }
+
+            Err(mut errors) => diagnostics.append(&mut errors),
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        warnings.append(&mut diagnostics);
+
+        return Err(warnings);
+    }
+
+    result.push_str(&entry_module.lua);
+
+    Ok((result, warnings))
+}
+
+fn compile_file(path: &str, options: &CompileOptions) -> Result<CompiledModule, Vec<Diagnostic>> {
+    match fs::read_to_string(path) {
+        Ok(content) => compile_source(path, &content, options),
+
+        Err(why) => Err(vec![response!(Wrong(format!(
+            "failed to read `{}`: {}",
+            path, why
+        )))]),
+    }
+}
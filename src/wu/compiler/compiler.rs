@@ -3,6 +3,7 @@ use super::*;
 use std::collections::HashMap;
 use std::path::Path;
 use std::ffi::OsStr;
+use std::rc::Rc;
 
 #[derive(Clone, PartialEq)]
 pub enum FlagImplicit {
@@ -11,8 +12,12 @@ pub enum FlagImplicit {
     Assign(String),
 }
 
+// Wrapped around the `line:column` a statement starts at, stripped once generated
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+
 #[derive(Clone, PartialEq)]
-pub enum Inside {
+enum Inside {
     Loop,
     //Nothing,
     Then,
@@ -28,14 +33,25 @@ pub struct Generator<'g> {
     special_break: bool,
 
     method_calls: &'g HashMap<Pos, bool>,
+    variant_patterns: &'g HashMap<Pos, bool>,
     import_map: &'g HashMap<Pos, (String, String)>,
+
+    // where the compiled tree ends up, local `require`s point into it
+    out_dir: Option<&'g str>,
+
+    // source position of each generated line
+    lines: Vec<Option<(usize, usize)>>,
+    // the name each import statement was `require`d by
+    requires: Vec<(Pos, String)>,
 }
 
 impl<'g> Generator<'g> {
     pub fn new(
         source: &'g Source,
         method_calls: &'g HashMap<Pos, bool>,
+        variant_patterns: &'g HashMap<Pos, bool>,
         import_map: &'g HashMap<Pos, (String, String)>,
+        out_dir: Option<&'g str>,
     ) -> Self {
         Generator {
             source,
@@ -47,7 +63,13 @@ impl<'g> Generator<'g> {
             special_break: false,
 
             method_calls,
+            variant_patterns,
             import_map,
+
+            out_dir,
+
+            lines: Vec::new(),
+            requires: Vec::new(),
         }
     }
 
@@ -84,16 +106,7 @@ impl<'g> Generator<'g> {
 
     pub fn generate(&mut self, ast: &'g Vec<Statement>) -> String {
         let mut result = "return (function()\n".to_string();
-        let mut output = String::new();
-
-        for statement in ast.iter() {
-            let line = self.generate_statement(&statement);
-            output.push_str(&line);
-
-            if line.trim().len() > 0 {
-                output.push('\n')
-            }
-        }
+        let output = self.generate_body(ast);
 
         self.push_line(&mut result, &output);
 
@@ -111,9 +124,95 @@ impl<'g> Generator<'g> {
 
         result.push_str("\nend)()");
 
+        self.unmark(&result)
+    }
+
+    // The statements alone, without wrapping them up as a module
+    pub fn generate_statements(&mut self, ast: &'g Vec<Statement>) -> String {
+        let output = self.generate_body(ast);
+
+        self.unmark(&output)
+    }
+
+    pub fn requires(&self) -> &Vec<(Pos, String)> {
+        &self.requires
+    }
+
+    // Where the lines of the last generated output came from
+    pub fn source_map(&self) -> SourceMap {
+        SourceMap::new(self.source.file.0.clone(), self.lines.clone())
+    }
+
+    // Strips the marks left by `generate_statement`, remembering them per line,
+    // lines without a mark of their own belong to the statement above
+    fn unmark(&mut self, output: &str) -> String {
+        let mut result = String::new();
+        let mut current = None;
+
+        self.lines.clear();
+
+        for line in output.split('\n') {
+            let mut rest = line;
+
+            while let Some(start) = rest.find(MARK_START) {
+                result.push_str(&rest[..start]);
+                rest = &rest[start + MARK_START.len_utf8()..];
+
+                match Self::read_mark(rest) {
+                    Some((mark, length)) => {
+                        current = Some(mark);
+                        rest = &rest[length..]
+                    }
+
+                    // Not one of ours, keep it as written
+                    None => result.push(MARK_START),
+                }
+            }
+
+            result.push_str(rest);
+            result.push('\n');
+
+            self.lines.push(current)
+        }
+
+        result.pop();
+
         result
     }
 
+    // Reads `line:column` up to the closing mark, with the length consumed
+    fn read_mark(rest: &str) -> Option<((usize, usize), usize)> {
+        let end = rest.find(MARK_END)?;
+        let (line, column) = rest[..end].split_once(':')?;
+
+        Some((
+            (line.parse().ok()?, column.parse().ok()?),
+            end + MARK_END.len_utf8(),
+        ))
+    }
+
+    // Literals can't carry the marks as they are, Lua gets them as escaped bytes
+    fn escape_marks(literal: &str) -> String {
+        literal
+            .replace(MARK_START, "\\238\\128\\128")
+            .replace(MARK_END, "\\238\\128\\129")
+    }
+
+    fn generate_body(&mut self, ast: &'g Vec<Statement>) -> String {
+        let mut output = String::new();
+
+        for statement in ast.iter() {
+            let line = self.generate_statement(&statement);
+            output.push_str(&line);
+
+            if line.trim().len() > 0 {
+                output.push('\n')
+            }
+        }
+
+        output
+    }
+
     fn generate_statement(&mut self, statement: &Statement) -> String {
         use self::StatementNode::*;
 
@@ -170,7 +269,7 @@ impl<'g> Generator<'g> {
                     self.flag = Some(FlagImplicit::Return);
 
                     let line = match expr.node {
-                        Block(..) | If(..) | While(..) => self.generate_expression(expr),
+                        Block(..) | If(..) | While(..) | Switch(..) => self.generate_expression(expr),
                         _ => format!("return {}", self.generate_expression(expr)),
                     };
 
@@ -195,7 +294,13 @@ impl<'g> Generator<'g> {
                     )
                 } else {
                     let my_folder = Path::new(&self.source.file.0).parent().unwrap();
-                    format!("{}/{}", my_folder.to_str().unwrap(), name)
+                    let mut my_folder = my_folder.to_path_buf();
+
+                    if let Some(out_dir) = self.out_dir {
+                        my_folder = Path::new(out_dir).join(my_folder)
+                    }
+
+                    my_folder.join(name).display().to_string()
                 };
 
                 if file_path.starts_with("./") {
@@ -216,9 +321,13 @@ impl<'g> Generator<'g> {
                         "package.path = package.path .. ';{0}?.lua;{0}?/init.lua'\n",
                         path
                     );
-                    result.push_str(&format!("local {0} = require('{0}')\n", name))
+                    result.push_str(&format!("local {0} = require('{0}')\n", name));
+
+                    self.requires.push((statement.pos.clone(), name.clone()))
                 } else {
-                    result = format!("local {} = require('{}')\n", name, real_path)
+                    result = format!("local {} = require('{}')\n", name, real_path);
+
+                    self.requires.push((statement.pos.clone(), real_path.clone()))
                 }
 
                 for specific in specifics {
@@ -279,7 +388,13 @@ impl<'g> Generator<'g> {
             }
         };
 
-        result
+        if result.trim().is_empty() {
+            result
+        } else {
+            let Pos((line, _), (column, _)) = statement.pos;
+
+            format!("{}{}:{}{}{}", MARK_START, line, column, MARK_END, result)
+        }
     }
 
     fn generate_expression(&mut self, expression: &Expression) -> String {
@@ -345,6 +460,11 @@ impl<'g> Generator<'g> {
 
                 self.flag = Some(FlagImplicit::Assign("none".to_string()));
                 let mut caller = self.generate_expression(called);
+
+                if let Function(..) = called.node {
+                    caller = format!("({})", caller)
+                }
+
                 let mut result = format!("{}(", caller);
 
                 let prefix = self.method_calls.get(&called.pos).is_some();
@@ -459,7 +579,7 @@ impl<'g> Generator<'g> {
                                     _ => match &self.flag.clone().unwrap() {
                                         &FlagImplicit::Return => {
                                             let line = match expression.node {
-                                                Block(..) | If(..) | While(..) => {
+                                                Block(..) | If(..) | While(..) | Switch(..) => {
                                                     self.generate_expression(expression)
                                                 }
 
@@ -555,7 +675,7 @@ impl<'g> Generator<'g> {
                 self.flag = Some(FlagImplicit::Return);
 
                 let line = match body.node {
-                    Block(..) | If(..) | While(..) => self.generate_expression(body),
+                    Block(..) | If(..) | While(..) | Switch(..) => self.generate_expression(body),
                     _ => format!("return {}", self.generate_expression(body)),
                 };
 
@@ -719,7 +839,7 @@ impl<'g> Generator<'g> {
                           }
                         }*/
 
-                        result.push_str(&self.make_line(&body_string));
+                        result.push_str(&body_string);
                     }
                 }
 
@@ -896,6 +1016,117 @@ impl<'g> Generator<'g> {
                 result
             }
 
+            // a block holding what's switched on, and an `if` per arm
+            Switch(ref subject, ref arms) => {
+                let Pos((line, _), (column, _)) = expression.pos;
+                let name = format!("__switch_tmp_{}_{}", line, column);
+
+                let tmp = Expression::new(Identifier(name.clone()), subject.pos.clone());
+
+                // tuples are several values in Lua, so they're packed to be taken apart by index
+                let packed = if arms.iter().any(|arm| Self::is_tuple_pattern(&arm.0)) {
+                    let flag_backup = self.flag.clone();
+
+                    self.flag = Some(FlagImplicit::Assign("none".to_string()));
+
+                    let lua = format!("{{ {} }}", self.generate_expression(subject));
+
+                    self.flag = flag_backup;
+
+                    Expression::new(
+                        Extern(Type::from(TypeNode::Any), Some(lua)),
+                        subject.pos.clone(),
+                    )
+                } else {
+                    (**subject).clone()
+                };
+
+                let mut branches = Vec::new();
+
+                for (pattern, guard, body) in arms.iter() {
+                    let mut bindings = Vec::new();
+
+                    let mut condition = self.pattern_condition(pattern, &tmp, &mut bindings);
+
+                    // the guard sees the pattern's names, so those get bound in a function of their own
+                    if let Some(ref guard) = *guard {
+                        let guard = if bindings.is_empty() {
+                            guard.clone()
+                        } else {
+                            let mut content = bindings.clone();
+
+                            content.push(Statement::new(
+                                StatementNode::Expression(guard.clone()),
+                                guard.pos.clone(),
+                            ));
+
+                            let function = Expression::new(
+                                Function(
+                                    Vec::new(),
+                                    Type::from(TypeNode::Bool),
+                                    Rc::new(Expression::new(Block(content), guard.pos.clone())),
+                                    false,
+                                ),
+                                guard.pos.clone(),
+                            );
+
+                            Expression::new(Call(Rc::new(function), Vec::new()), guard.pos.clone())
+                        };
+
+                        condition = Some(match condition {
+                            Some(condition) => Expression::new(
+                                Binary(Rc::new(condition), Operator::And, Rc::new(guard)),
+                                pattern.pos.clone(),
+                            ),
+
+                            None => guard,
+                        })
+                    }
+
+                    let condition = condition
+                        .unwrap_or_else(|| Expression::new(Bool(true), pattern.pos.clone()));
+
+                    let body = if let Block(ref content) = body.node {
+                        bindings.extend(content.iter().cloned());
+
+                        Expression::new(Block(bindings), body.pos.clone())
+                    } else {
+                        unreachable!()
+                    };
+
+                    branches.push((Some(condition), body, pattern.pos.clone()))
+                }
+
+                let (condition, body, position) = branches.remove(0);
+
+                let chain = Expression::new(
+                    If(
+                        Rc::new(condition.unwrap()),
+                        Rc::new(body),
+                        if branches.is_empty() { None } else { Some(branches) },
+                    ),
+                    position.clone(),
+                );
+
+                let lowered = Expression::new(
+                    Block(vec![
+                        Statement::new(
+                            StatementNode::Variable(
+                                Type::from(TypeNode::Nil),
+                                name,
+                                Some(packed),
+                                false,
+                            ),
+                            subject.pos.clone(),
+                        ),
+                        Statement::new(StatementNode::Expression(chain), position),
+                    ]),
+                    expression.pos.clone(),
+                );
+
+                self.generate_expression(&lowered)
+            }
+
             Initialization(ref name, ref body) => {
                 let mut inner = String::new();
 
@@ -925,8 +1156,11 @@ impl<'g> Generator<'g> {
             Int(ref n) => format!("{}", n),
             Float(ref n) => format!("{}", n),
             Bool(ref n) => format!("{}", n),
-            Str(ref n) => format!("\"{}\"", n.replace("\\n", "\\\\n").replace('\n', "\\n")),
-            Char(ref n) => format!("\"{}\"", n),
+            Str(ref n) => format!(
+                "\"{}\"",
+                Self::escape_marks(&n.replace("\\n", "\\\\n").replace('\n', "\\n"))
+            ),
+            Char(ref n) => format!("\"{}\"", Self::escape_marks(&n.to_string())),
             Identifier(ref n) => Self::make_valid(n),
 
             Cast(ref a, ref t) => {
@@ -1002,6 +1236,7 @@ impl<'g> Generator<'g> {
             } else {
                 let right_str = match right.node {
                     ExpressionNode::Struct(..) => "{}".to_string(),
+                    ExpressionNode::Enum(_, ref variants, _) => self.generate_enum(variants),
                     ExpressionNode::Extern(_, ref lua) if lua.is_none() => return String::new(),
                     ExpressionNode::Trait(..) | ExpressionNode::ExternExpression(..) => return String::new(),
                     
@@ -1017,6 +1252,227 @@ impl<'g> Generator<'g> {
         format!("{}", result)
     }
 
+    // Variants are tables tagged with their name, the payload following in order
+    fn generate_enum(&mut self, variants: &[(String, Vec<Type>)]) -> String {
+        let mut inner = String::new();
+
+        for (name, payload) in variants.iter() {
+            if payload.is_empty() {
+                inner.push_str(&format!("{0} = {{ tag = \"{0}\" }},\n", name))
+            } else {
+                inner.push_str(&format!(
+                    "{0} = function(...) return {{ tag = \"{0}\", ... }} end,\n",
+                    name
+                ))
+            }
+        }
+
+        format!("{{\n{}}}", self.make_line(&inner))
+    }
+
+    // What `subject` has to be for a pattern to match, the names it binds going into `bindings`
+    fn pattern_condition(
+        &self,
+        pattern: &Pattern,
+        subject: &Expression,
+        bindings: &mut Vec<Statement>,
+    ) -> Option<Expression> {
+        use self::ExpressionNode::*;
+
+        let position = pattern.pos.clone();
+
+        match pattern.node {
+            PatternNode::Wildcard => None,
+
+            PatternNode::Binding(ref name) => {
+                if self.variant_patterns.contains_key(&pattern.pos) {
+                    return Some(Self::tag_check(subject, name, position));
+                }
+
+                bindings.push(Statement::new(
+                    StatementNode::Variable(
+                        Type::from(TypeNode::Nil),
+                        name.clone(),
+                        Some(subject.clone()),
+                        false,
+                    ),
+                    position,
+                ));
+
+                None
+            }
+
+            PatternNode::Value(ref value) => {
+                if let Index(_, ref index, false) = value.node {
+                    if let Identifier(ref name) = index.node {
+                        if self.variant_patterns.contains_key(&pattern.pos) {
+                            return Some(Self::tag_check(subject, name, position));
+                        }
+                    }
+                }
+
+                Some(Expression::new(
+                    Binary(Rc::new(value.clone()), Operator::Eq, Rc::new(subject.clone())),
+                    position,
+                ))
+            }
+
+            PatternNode::Range(ref start, ref end) => {
+                let above = Expression::new(
+                    Binary(Rc::new(start.clone()), Operator::LtEq, Rc::new(subject.clone())),
+                    position.clone(),
+                );
+
+                let below = Expression::new(
+                    Binary(Rc::new(subject.clone()), Operator::Lt, Rc::new(end.clone())),
+                    position.clone(),
+                );
+
+                Some(Expression::new(
+                    Binary(Rc::new(above), Operator::And, Rc::new(below)),
+                    position,
+                ))
+            }
+
+            PatternNode::Tuple(ref content) => {
+                let parts = content
+                    .iter()
+                    .enumerate()
+                    .map(|(i, element)| {
+                        let index = Expression::new(Int(i as i64 + 1), element.pos.clone());
+
+                        let part = Expression::new(
+                            Index(Rc::new(subject.clone()), Rc::new(index), true),
+                            element.pos.clone(),
+                        );
+
+                        (element, part)
+                    })
+                    .collect::<Vec<_>>();
+
+                self.all_conditions(&parts, None, bindings, position)
+            }
+
+            PatternNode::Struct(_, ref fields) => {
+                let parts = fields
+                    .iter()
+                    .map(|(name, field)| {
+                        let member = Expression::new(Identifier(name.clone()), field.pos.clone());
+
+                        let part = Expression::new(
+                            Index(Rc::new(subject.clone()), Rc::new(member), false),
+                            field.pos.clone(),
+                        );
+
+                        (field, part)
+                    })
+                    .collect::<Vec<_>>();
+
+                self.all_conditions(&parts, None, bindings, position)
+            }
+
+            PatternNode::Variant(ref name, ref fields) => {
+                let parts = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let index = Expression::new(Int(i as i64 + 1), field.pos.clone());
+
+                        let part = Expression::new(
+                            Index(Rc::new(subject.clone()), Rc::new(index), true),
+                            field.pos.clone(),
+                        );
+
+                        (field, part)
+                    })
+                    .collect::<Vec<_>>();
+
+                let tag = Self::tag_check(subject, name, position.clone());
+
+                self.all_conditions(&parts, Some(tag), bindings, position)
+            }
+
+            // alternatives bind nothing, the visitor made sure of that
+            PatternNode::Alternative(ref alternatives) => {
+                let mut condition: Option<Expression> = None;
+
+                for alternative in alternatives.iter() {
+                    let part = self.pattern_condition(alternative, subject, &mut Vec::new())?;
+
+                    condition = Some(match condition {
+                        Some(condition) => Expression::new(
+                            Binary(Rc::new(condition), Operator::Or, Rc::new(part)),
+                            position.clone(),
+                        ),
+
+                        None => part,
+                    })
+                }
+
+                condition
+            }
+        }
+    }
+
+    // Every part's condition and-ed onto `first`, if any of them has one
+    fn all_conditions(
+        &self,
+        parts: &[(&Pattern, Expression)],
+        first: Option<Expression>,
+        bindings: &mut Vec<Statement>,
+        position: Pos,
+    ) -> Option<Expression> {
+        let mut condition = first;
+
+        for (pattern, part) in parts.iter() {
+            if let Some(part_condition) = self.pattern_condition(pattern, part, bindings) {
+                condition = Some(match condition {
+                    Some(condition) => Expression::new(
+                        ExpressionNode::Binary(
+                            Rc::new(condition),
+                            Operator::And,
+                            Rc::new(part_condition),
+                        ),
+                        position.clone(),
+                    ),
+
+                    None => part_condition,
+                })
+            }
+        }
+
+        condition
+    }
+
+    fn is_tuple_pattern(pattern: &Pattern) -> bool {
+        match pattern.node {
+            PatternNode::Tuple(_) => true,
+            PatternNode::Alternative(ref alternatives) => {
+                alternatives.iter().any(Self::is_tuple_pattern)
+            }
+
+            _ => false,
+        }
+    }
+
+    fn tag_check(subject: &Expression, name: &str, position: Pos) -> Expression {
+        use self::ExpressionNode::*;
+
+        let tag = Expression::new(Identifier("tag".to_string()), position.clone());
+
+        Expression::new(
+            Binary(
+                Rc::new(Expression::new(
+                    Index(Rc::new(subject.clone()), Rc::new(tag), false),
+                    position.clone(),
+                )),
+                Operator::Eq,
+                Rc::new(Expression::new(Str(name.to_string()), position.clone())),
+            ),
+            position,
+        )
+    }
+
     fn generate_assignment<'b>(&mut self, left: &'b Expression, right: &'b Expression) -> String {
         let left_string = self.generate_expression(left);
 
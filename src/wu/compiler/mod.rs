@@ -1,4 +1,5 @@
 pub mod compiler;
+pub mod sourcemap;
 
 use super::lexer::*;
 use super::parser::*;
@@ -6,3 +7,4 @@ use super::source::*;
 use super::visitor::*;
 
 pub use self::compiler::*;
+pub use self::sourcemap::*;
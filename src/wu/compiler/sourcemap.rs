@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+// Which `.wu` line and column each line of generated Lua came from
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SourceMap {
+    pub source: String,
+    pub lines: Vec<Option<(usize, usize)>>,
+}
+
+impl SourceMap {
+    pub fn new(source: String, lines: Vec<Option<(usize, usize)>>) -> Self {
+        SourceMap { source, lines }
+    }
+
+    // `lua_line` is 1-based, like in Lua's tracebacks
+    pub fn lookup(&self, lua_line: usize) -> Option<(usize, usize)> {
+        if lua_line == 0 {
+            return None;
+        }
+
+        self.lines.get(lua_line - 1).cloned().flatten()
+    }
+
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+
+        let source = lines.next()?.strip_prefix("source ")?.to_string();
+
+        let mut map = Vec::new();
+
+        for line in lines {
+            if line == "-" {
+                map.push(None);
+
+                continue;
+            }
+
+            let mut split = line.splitn(2, ':');
+
+            let line = split.next()?.parse::<usize>().ok()?;
+            let column = split.next()?.parse::<usize>().ok()?;
+
+            map.push(Some((line, column)))
+        }
+
+        Some(SourceMap::new(source, map))
+    }
+}
+
+// The `.lua.map` format: a `source` line, then one `line:column` or `-` per Lua line
+impl fmt::Display for SourceMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "source {}", self.source)?;
+
+        for line in self.lines.iter() {
+            match *line {
+                Some((line, column)) => writeln!(f, "{}:{}", line, column)?,
+                None => writeln!(f, "-")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Rewrites every `file.lua:line` with a `.lua.map` next to it into `file.wu:line`
+pub fn trace(text: &str) -> String {
+    let mut maps: HashMap<String, Option<SourceMap>> = HashMap::new();
+
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(index) = rest.find(".lua:") {
+        let (before, after) = rest.split_at(index);
+
+        let start = before
+            .rfind(|c: char| c.is_whitespace() || "\"'()[]<>".contains(c))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+
+        let digits = after[5..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len() - 5);
+
+        let path = format!("{}.lua", &before[start..]);
+
+        let map = maps.entry(path.clone()).or_insert_with(|| {
+            fs::read_to_string(format!("{}.map", path))
+                .ok()
+                .and_then(|content| SourceMap::parse(&content))
+        });
+
+        let found = after[5..5 + digits]
+            .parse::<usize>()
+            .ok()
+            .and_then(|line| map.as_ref().and_then(|map| Some((&map.source, map.lookup(line)?))));
+
+        match found {
+            Some((source, (line, _))) => {
+                result.push_str(&before[..start]);
+                result.push_str(&format!("{}:{}", source, line));
+            }
+
+            None => {
+                result.push_str(before);
+                result.push_str(&after[..5 + digits]);
+            }
+        }
+
+        rest = &after[5 + digits..];
+    }
+
+    result.push_str(rest);
+
+    result
+}
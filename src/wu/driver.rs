@@ -0,0 +1,262 @@
+use super::compiler::*;
+use super::error::Diagnostic;
+use super::lexer::*;
+use super::parser::*;
+use super::source::*;
+use super::visitor::*;
+
+pub struct CompileOptions {
+    // directory that deep imports are resolved against
+    pub root: String,
+    // directory the compiled tree is mirrored into, `None` meaning beside the sources
+    pub out_dir: Option<String>,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            root: ".".to_string(),
+            out_dir: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CompiledModule {
+    // left empty when only checking
+    pub lua: String,
+    pub map: SourceMap,
+    // what the Lua `require`s, along with the .wu file behind each name
+    pub requires: Vec<(String, String)>,
+    // what was weird but not wrong enough to stop compiling
+    pub warnings: Vec<Diagnostic>,
+}
+
+// Runs the whole pipeline on in-memory source without printing anything,
+// `name` is the path used for diagnostics and for resolving relative imports
+pub fn compile_source(
+    name: &str,
+    text: &str,
+    options: &CompileOptions,
+) -> Result<CompiledModule, Vec<Diagnostic>> {
+    run(name, text, options, true)
+}
+
+// Same as `compile_source`, stopping once the visitor is happy, handing back its warnings
+pub fn check_source(
+    name: &str,
+    text: &str,
+    options: &CompileOptions,
+) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+    run(name, text, options, false).map(|module| module.warnings)
+}
+
+// Compiles input piece by piece, declarations sticking around between pieces
+pub struct Session {
+    symtab: SymTab,
+    options: CompileOptions,
+}
+
+pub struct Evaluated {
+    // type of the trailing expression, if the input ended with one
+    pub kind: Option<Type>,
+    // names declared by the input along with their types
+    pub bindings: Vec<(String, Type)>,
+    pub lua: String,
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl Session {
+    pub fn new(options: CompileOptions) -> Self {
+        Session {
+            symtab: prelude(),
+            options,
+        }
+    }
+
+    // Nothing is kept when the input turns out to be wrong
+    pub fn eval(&mut self, text: &str) -> Result<Evaluated, Vec<Diagnostic>> {
+        let source = Source::from(
+            "./<repl>",
+            text.lines().map(|x| x.into()).collect::<Vec<String>>(),
+        );
+        let lexer = Lexer::default(text.chars().collect(), &source);
+
+        let mut tokens = Vec::new();
+
+        for token_result in lexer {
+            tokens.push(token_result.map_err(|diagnostic| vec![diagnostic])?)
+        }
+
+        let ast = Parser::new(tokens, &source).parse()?;
+
+        let mut visitor = Visitor::from_symtab(
+            &ast,
+            &source,
+            self.symtab.clone(),
+            self.options.root.clone(),
+        );
+
+        let warnings = visitor.visit()?;
+
+        let mut kind = None;
+        let mut bindings = Vec::new();
+
+        for statement in ast.iter() {
+            match statement.node {
+                StatementNode::Variable(_, ref name, ..) => {
+                    bindings.extend(visitor.symtab.fetch(name).map(|t| (name.clone(), t)))
+                }
+
+                StatementNode::SplatVariable(_, ref names, ..) => {
+                    for name in names {
+                        bindings.extend(visitor.symtab.fetch(name).map(|t| (name.clone(), t)))
+                    }
+                }
+
+                _ => (),
+            }
+        }
+
+        if let Some(Statement {
+            node: StatementNode::Expression(ref expression),
+            ..
+        }) = ast.last()
+        {
+            kind = Some(
+                visitor
+                    .type_expression(expression)
+                    .map_err(|diagnostic| vec![diagnostic])?,
+            )
+        }
+
+        let lua = Generator::new(
+            &source,
+            &visitor.method_calls,
+            &visitor.variant_patterns,
+            &visitor.import_map,
+            self.options.out_dir.as_deref(),
+        )
+        .generate_statements(&ast);
+
+        self.symtab = visitor.symtab;
+
+        Ok(Evaluated {
+            kind,
+            bindings,
+            lua,
+            warnings,
+        })
+    }
+}
+
+// Names of the modules a file imports, found without parsing so that broken files still count
+pub fn imports(name: &str, text: &str) -> Vec<String> {
+    let source = Source::from(
+        name,
+        text.lines().map(|x| x.into()).collect::<Vec<String>>(),
+    );
+    let lexer = Lexer::default(text.chars().collect(), &source);
+
+    let mut imports = Vec::new();
+    let mut after_import = false;
+
+    for token in lexer {
+        let token = match token {
+            Ok(token) => token,
+            Err(_) => break,
+        };
+
+        if after_import && token.token_type == TokenType::Identifier {
+            imports.push(token.lexeme.clone())
+        }
+
+        after_import = token.token_type == TokenType::Keyword && token.lexeme == "import"
+    }
+
+    imports
+}
+
+// The symbols every Wu file gets for free
+pub fn prelude() -> SymTab {
+    let mut symtab = SymTab::new();
+
+    let splat_any = Type::new(TypeNode::Any, TypeMode::Splat(None));
+
+    symtab.assign_str(
+        "print",
+        Type::function(vec![splat_any.clone()], Type::from(TypeNode::Nil), false),
+    );
+
+    symtab.assign_str(
+        "ipairs",
+        Type::function(vec![splat_any.clone()], splat_any.clone(), false),
+    );
+
+    symtab.assign_str(
+        "pairs",
+        Type::function(vec![splat_any.clone()], splat_any, false),
+    );
+
+    symtab
+}
+
+fn run(
+    name: &str,
+    content: &str,
+    options: &CompileOptions,
+    generate: bool,
+) -> Result<CompiledModule, Vec<Diagnostic>> {
+    let source = Source::from(
+        name,
+        content.lines().map(|x| x.into()).collect::<Vec<String>>(),
+    );
+    let lexer = Lexer::default(content.chars().collect(), &source);
+
+    let mut tokens = Vec::new();
+
+    for token_result in lexer {
+        tokens.push(token_result.map_err(|diagnostic| vec![diagnostic])?)
+    }
+
+    let ast = Parser::new(tokens, &source).parse()?;
+
+    let mut visitor = Visitor::from_symtab(&ast, &source, prelude(), options.root.clone());
+
+    let warnings = visitor.visit()?;
+
+    if !generate {
+        return Ok(CompiledModule {
+            warnings,
+            ..Default::default()
+        });
+    }
+
+    let mut generator = Generator::new(
+        &source,
+        &visitor.method_calls,
+        &visitor.variant_patterns,
+        &visitor.import_map,
+        options.out_dir.as_deref(),
+    );
+
+    let lua = generator.generate(&ast);
+
+    let requires = generator
+        .requires()
+        .iter()
+        .filter_map(|(pos, name)| {
+            visitor
+                .modules
+                .get(pos)
+                .map(|file| (name.clone(), file.clone()))
+        })
+        .collect();
+
+    Ok(CompiledModule {
+        lua,
+        map: generator.source_map(),
+        requires,
+        warnings,
+    })
+}
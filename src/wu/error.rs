@@ -1,6 +1,9 @@
 use colored::Colorize;
 use std::fmt;
 
+use super::lexer::Pos;
+use super::source::FilePath;
+
 pub enum Response<T: fmt::Display> {
     Wrong(T),
     Weird(T),
@@ -9,13 +12,15 @@ pub enum Response<T: fmt::Display> {
 
 use self::Response::*;
 
+// Builds a `Diagnostic` out of `Response`s, a `FilePath`, a primary `Pos` and any `Label`s
 #[macro_export]
 macro_rules! response {
   ( $( $r:expr ),+ ) => {{
+    let mut diagnostic = $crate::wu::error::Diagnostic::new();
     $(
-        print!("{}", $r);
+        $crate::wu::error::Report::report(&$r, &mut diagnostic);
     )*
-    println!();
+    diagnostic
   }};
 }
 
@@ -35,3 +40,265 @@ impl<T: fmt::Display> fmt::Display for Response<T> {
         write!(f, "{}", message)
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Severity {
+    Wrong,
+    Weird,
+}
+
+// A secondary span pointing at something related to the primary one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label(pub Pos, pub String);
+
+impl Label {
+    pub fn new<T: fmt::Display>(pos: Pos, message: T) -> Self {
+        Label(pos, message.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<FilePath>,
+    pub span: Option<Pos>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new() -> Self {
+        Diagnostic {
+            severity: Severity::Wrong,
+            message: String::new(),
+            file: None,
+            span: None,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn is_wrong(&self) -> bool {
+        self.severity == Severity::Wrong
+    }
+}
+
+impl Default for Diagnostic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Severity::Wrong => "wrong",
+            Severity::Weird => "weird",
+        }
+    }
+}
+
+impl Diagnostic {
+    // One line of JSON, for tools that would rather not scrape the coloured output
+    pub fn to_json(&self) -> String {
+        let file = match self.file {
+            Some(FilePath(ref path)) => json_string(path),
+            None => "null".to_string(),
+        };
+
+        let span = match self.span {
+            Some(ref pos) => json_span(pos),
+            None => "null".to_string(),
+        };
+
+        let labels = self
+            .labels
+            .iter()
+            .map(|Label(pos, message)| {
+                format!(
+                    "{{\"span\":{},\"message\":{}}}",
+                    json_span(pos),
+                    json_string(message)
+                )
+            })
+            .collect::<Vec<String>>();
+
+        let notes = self
+            .notes
+            .iter()
+            .map(|note| json_string(note))
+            .collect::<Vec<String>>();
+
+        format!(
+            "{{\"file\":{},\"severity\":{},\"message\":{},\"span\":{},\"labels\":[{}],\"notes\":[{}]}}",
+            file,
+            json_string(self.severity.as_str()),
+            json_string(&self.message),
+            span,
+            labels.join(","),
+            notes.join(",")
+        )
+    }
+}
+
+// Columns are 1-based, `column_end` being the last column covered
+fn json_span(pos: &Pos) -> String {
+    let Pos((line, _), (start, end)) = pos;
+
+    format!(
+        "{{\"line\":{},\"column_start\":{},\"column_end\":{}}}",
+        line, start, end
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+
+    result.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+
+    result
+}
+
+// The coloured format `wu` has always printed
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            Severity::Wrong => write!(f, "{}", Wrong(&self.message))?,
+            Severity::Weird => write!(f, "{}", Weird(&self.message))?,
+        }
+
+        if let Some(ref file) = self.file {
+            write!(f, "{}", file)?
+        }
+
+        if let Some(ref span) = self.span {
+            write!(f, "{}", span)?
+        }
+
+        for Label(pos, message) in self.labels.iter() {
+            write!(f, "{} {}", pos, message.cyan().bold())?
+        }
+
+        for note in self.notes.iter() {
+            write!(f, "{}", Note(note))?
+        }
+
+        Ok(())
+    }
+}
+
+// Drops repeated diagnostics, which show up when recovering stages revisit code
+pub fn dedup(diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen: Vec<Diagnostic> = Vec::new();
+
+    diagnostics.retain(|diagnostic| {
+        if seen.contains(diagnostic) {
+            false
+        } else {
+            seen.push(diagnostic.clone());
+            true
+        }
+    });
+}
+
+// Anything that can be handed to `response!`
+pub trait Report {
+    fn report(&self, diagnostic: &mut Diagnostic);
+}
+
+impl<T: fmt::Display> Report for Response<T> {
+    fn report(&self, diagnostic: &mut Diagnostic) {
+        match *self {
+            Wrong(ref m) => {
+                diagnostic.severity = Severity::Wrong;
+                diagnostic.message = m.to_string()
+            }
+
+            Weird(ref m) => {
+                diagnostic.severity = Severity::Weird;
+                diagnostic.message = m.to_string()
+            }
+
+            Note(ref m) => diagnostic.notes.push(m.to_string()),
+        }
+    }
+}
+
+impl Report for FilePath {
+    fn report(&self, diagnostic: &mut Diagnostic) {
+        diagnostic.file = Some(self.clone())
+    }
+}
+
+impl Report for Pos {
+    fn report(&self, diagnostic: &mut Diagnostic) {
+        diagnostic.span = Some(self.clone())
+    }
+}
+
+impl Report for Label {
+    fn report(&self, diagnostic: &mut Diagnostic) {
+        diagnostic.labels.push(self.clone())
+    }
+}
+
+impl<T: Report + ?Sized> Report for &T {
+    fn report(&self, diagnostic: &mut Diagnostic) {
+        (**self).report(diagnostic)
+    }
+}
+
+// Somewhere for diagnostics to go
+pub trait Emitter {
+    fn emit(&mut self, diagnostic: &Diagnostic);
+
+    // Progress meant for humans, wherever it doesn't get in the way
+    fn status(&self, message: &str) {
+        println!("{}", message)
+    }
+}
+
+// Prints diagnostics the way humans like them
+pub struct Terminal;
+
+impl Emitter for Terminal {
+    fn emit(&mut self, diagnostic: &Diagnostic) {
+        println!("{}", diagnostic)
+    }
+}
+
+// Prints diagnostics as JSON, one object per line
+pub struct Json;
+
+impl Emitter for Json {
+    fn emit(&mut self, diagnostic: &Diagnostic) {
+        println!("{}", diagnostic.to_json())
+    }
+
+    // stdout is only for JSON
+    fn status(&self, message: &str) {
+        eprintln!("{}", message)
+    }
+}
+
+// Keeps diagnostics around for later
+impl Emitter for Vec<Diagnostic> {
+    fn emit(&mut self, diagnostic: &Diagnostic) {
+        self.push(diagnostic.clone())
+    }
+}
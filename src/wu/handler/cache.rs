@@ -0,0 +1,182 @@
+use git2::{Direction, FetchOptions, RemoteCallbacks, Repository};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Where the cache lives inside `$WU_HOME`, not a name `import` could ever reach
+pub const CACHE_DIR: &str = ".cache";
+
+// Lists the commits checked out of a mirror, one per line
+const COMMITS_FILE: &str = "wu-commits";
+
+// `$WU_HOME/.cache/`, if there is a `$WU_HOME`
+pub fn root() -> Option<PathBuf> {
+    let home = env::var("WU_HOME").ok()?;
+
+    Some(Path::new(&home).join(CACHE_DIR))
+}
+
+// A bare copy of a remote shared by every project, git storing commits by their hash
+// makes a URL and commit enough to find anything in it
+pub struct Mirror {
+    pub path: PathBuf,
+    pub url: String,
+    repo: Repository,
+}
+
+impl Mirror {
+    // The mirror of `url`, made empty if it's not around yet
+    pub fn open(root: &Path, url: &str) -> Result<Self, git2::Error> {
+        let path = root.join(entry_name(url));
+
+        let repo = match Repository::open_bare(&path) {
+            Ok(repo) => repo,
+
+            Err(_) => {
+                let repo = Repository::init_bare(&path)?;
+
+                repo.remote("origin", url)?;
+                repo
+            }
+        };
+
+        Ok(Mirror {
+            path,
+            url: url.to_string(),
+            repo,
+        })
+    }
+
+    // The mirror of `url` if there is one, never making one
+    pub fn find(root: &Path, url: &str) -> Option<Self> {
+        let path = root.join(entry_name(url));
+        let repo = Repository::open_bare(&path).ok()?;
+
+        Some(Mirror {
+            path,
+            url: url.to_string(),
+            repo,
+        })
+    }
+
+    pub fn has(&self, commit: &str) -> bool {
+        self.repo
+            .revparse_single(commit)
+            .and_then(|object| object.peel_to_commit())
+            .is_ok()
+    }
+
+    // Brings every branch and tag up to date with the remote, its `HEAD` included
+    pub fn fetch(&self) -> Result<(), git2::Error> {
+        let mut remote = self.repo.find_remote("origin")?;
+
+        remote.connect(Direction::Fetch)?;
+
+        let head = remote
+            .default_branch()
+            .ok()
+            .and_then(|head| head.as_str().map(String::from));
+
+        remote.disconnect()?;
+
+        let cb = RemoteCallbacks::new();
+        let mut fo = FetchOptions::new();
+
+        fo.remote_callbacks(cb);
+
+        remote.fetch(
+            &["+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"],
+            Some(&mut fo),
+            None,
+        )?;
+
+        if let Some(head) = head {
+            self.repo.set_head(&head)?
+        }
+
+        Ok(())
+    }
+
+    // The branch `HEAD` points at, like `refs/heads/master`
+    pub fn head(&self) -> Option<String> {
+        self.repo
+            .find_reference("HEAD")
+            .ok()?
+            .symbolic_target()
+            .map(String::from)
+    }
+
+    // Remembers that a project checked out `commit`, for `wu cache list`
+    pub fn record(&self, commit: &str) {
+        let mut commits = self.commits();
+
+        if !commits.iter().any(|known| known == commit) {
+            commits.push(commit.to_string());
+
+            let _ = fs::write(self.path.join(COMMITS_FILE), commits.join("\n") + "\n");
+        }
+    }
+
+    pub fn commits(&self) -> Vec<String> {
+        fs::read_to_string(self.path.join(COMMITS_FILE))
+            .unwrap_or_default()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+// Every mirror in the cache
+pub fn mirrors(root: &Path) -> Vec<Mirror> {
+    let mut mirrors = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            let repo = match Repository::open_bare(&path) {
+                Ok(repo) => repo,
+                Err(_) => continue,
+            };
+
+            let url = repo
+                .find_remote("origin")
+                .ok()
+                .and_then(|remote| remote.url().map(String::from))
+                .unwrap_or_default();
+
+            mirrors.push(Mirror { path, url, repo })
+        }
+    }
+
+    mirrors.sort_by(|a, b| a.url.cmp(&b.url));
+    mirrors
+}
+
+// Readable, and different for every URL: `repo-` and a hash of the URL
+fn entry_name(url: &str) -> String {
+    let name = url
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect::<String>();
+
+    format!("{}-{:016x}", name, fnv(url))
+}
+
+// FNV-1a, which unlike the standard hasher is the same everywhere and forever
+fn fnv(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
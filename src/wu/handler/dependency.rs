@@ -0,0 +1,159 @@
+use toml::Value;
+
+use std::fmt;
+
+// What to check out of a git dependency
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    // whatever the remote's HEAD points at
+    Default,
+    Tag(String),
+    Branch(String),
+    Rev(String),
+}
+
+impl Reference {
+    // Reads the `tag`, `branch` or `rev` key of a table, at most one of them
+    pub fn from_table(table: &Value) -> Result<Self, String> {
+        let mut found = Vec::new();
+
+        for key in ["tag", "branch", "rev"].iter() {
+            match table.get(*key) {
+                Some(Value::String(ref value)) => found.push((*key, value.clone())),
+                Some(_) => return Err(format!("expected string `{}` value", key)),
+                None => (),
+            }
+        }
+
+        if found.len() > 1 {
+            return Err("expected only one of `tag`, `branch` or `rev`".to_string());
+        }
+
+        Ok(match found.pop() {
+            Some(("tag", tag)) => Reference::Tag(tag),
+            Some(("branch", branch)) => Reference::Branch(branch),
+            Some((_, rev)) => Reference::Rev(rev),
+            None => Reference::Default,
+        })
+    }
+
+    // The key and value it's written as, if any
+    pub fn key_value(&self) -> Option<(&'static str, &str)> {
+        match *self {
+            Reference::Default => None,
+            Reference::Tag(ref tag) => Some(("tag", tag)),
+            Reference::Branch(ref branch) => Some(("branch", branch)),
+            Reference::Rev(ref rev) => Some(("rev", rev)),
+        }
+    }
+
+    // What to look up in a checkout once fetched
+    pub fn spec(&self) -> String {
+        match *self {
+            Reference::Default => "refs/remotes/origin/HEAD".to_string(),
+            Reference::Tag(ref tag) => format!("refs/tags/{}", tag),
+            Reference::Branch(ref branch) => format!("refs/remotes/origin/{}", branch),
+            Reference::Rev(ref rev) => rev.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.key_value() {
+            Some((key, value)) => write!(f, "{} `{}`", key, value),
+            None => write!(f, "default branch"),
+        }
+    }
+}
+
+// Where a dependency comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Git(String, Reference),
+    // a directory on disk, linked into the libpath
+    Path(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Origin::Git(ref url, ref reference) => write!(f, "`{}` ({})", url, reference),
+            Origin::Path(ref path) => write!(f, "path `{}`", path),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub origin: Origin,
+}
+
+impl Dependency {
+    // Either `name = "user/repo"`, `name = { git = "user/repo" or url, tag = "v1" }`
+    // or `name = { path = "../name" }`
+    pub fn from_toml(name: &str, value: &Value) -> Result<Self, String> {
+        let origin = match *value {
+            Value::String(ref repo) => Origin::Git(git_url(repo), Reference::Default),
+
+            Value::Table(_) => match (value.get("git"), value.get("path")) {
+                (Some(_), Some(_)) => {
+                    return Err(format!(
+                        "expected only one of `git` or `path` for `{}`",
+                        name
+                    ))
+                }
+
+                (Some(Value::String(ref repo)), None) => {
+                    let reference = Reference::from_table(value)
+                        .map_err(|why| format!("{} for `{}`", why, name))?;
+
+                    Origin::Git(git_url(repo), reference)
+                }
+
+                (None, Some(Value::String(ref path))) => {
+                    if Reference::from_table(value) != Ok(Reference::Default) {
+                        return Err(format!(
+                            "expected no `tag`, `branch` or `rev` for path dependency `{}`",
+                            name
+                        ));
+                    }
+
+                    Origin::Path(path.clone())
+                }
+
+                (Some(_), None) => {
+                    return Err(format!("expected string `git` value for `{}`", name))
+                }
+
+                (None, Some(_)) => {
+                    return Err(format!("expected string `path` value for `{}`", name))
+                }
+
+                (None, None) => return Err(format!("expected `git` or `path` key for `{}`", name)),
+            },
+
+            _ => {
+                return Err(format!(
+                    r#"expected `{0} = "user/repo"` or `{0} = {{ git = "user/repo", tag = "v1" }}`"#,
+                    name
+                ))
+            }
+        };
+
+        Ok(Dependency {
+            name: name.to_string(),
+            origin,
+        })
+    }
+}
+
+// `user/repo` is short for GitHub, anything that looks like a url is left alone
+pub fn git_url(repo: &str) -> String {
+    if repo.contains("://") || repo.starts_with("git@") {
+        repo.to_string()
+    } else {
+        format!("https://github.com/{}", repo)
+    }
+}
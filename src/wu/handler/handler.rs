@@ -1,125 +1,1542 @@
 use git2::build::{CheckoutBuilder, RepoBuilder};
-use git2::{FetchOptions, RemoteCallbacks};
+use git2::{FetchOptions, RemoteCallbacks, Repository, StatusOptions};
 
 use toml::Value;
 
+use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::Write;
 
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 
 use colored::Colorize;
 
-pub fn new(name: Option<&str>) {
-    if let Some(name) = name {
-        if Path::new(name).exists() {
-            wrong(&format!("path '{}' already exists", name));
-        } else {
-            fs::create_dir_all(format!("{}/src", name)).unwrap();
+use super::cache::{self, Mirror};
+use super::dependency::*;
+use super::lock::*;
 
-            let mut init = File::create(&format!("{}/init.wu", name)).unwrap();
-            init.write_all(b"import src\n").unwrap();
+use super::manifest::{self, Manifest};
+use super::template::{self, Template};
 
-            let mut wu_toml = File::create(&format!("{}/wu.toml", name)).unwrap();
-            wu_toml.write_all(b"[dependencies]\n").unwrap();
+use super::super::error::{Emitter, Response};
 
-            File::create(&format!("{}/src/init.wu", name)).unwrap();
+pub const VENDOR_DIR: &str = "vendor";
+// the lock as it was when vendoring
+const VENDOR_LOCK: &str = "vendor/wu.lock";
+
+pub fn new(name: Option<&str>, template: Option<&str>, emitter: &mut dyn Emitter) {
+    let template_name = template.unwrap_or("app");
+
+    let template = match Template::find(template_name) {
+        Some(template) => template,
+        None => {
+            let place = template::user_dir()
+                .map(|dir| format!("`{}`", dir.display()))
+                .unwrap_or_else(|| "`$WU_HOME/templates`".to_string());
+
+            return wrong(
+                emitter,
+                &format!(
+                    "no template called `{}`, there's {} and whatever is in {}",
+                    template_name,
+                    template::BUILT_IN
+                        .iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    place
+                ),
+            );
         }
-    } else {
-        let mut wu_toml = File::create("wu.toml").unwrap();
-        wu_toml.write_all(b"[dependencies]").unwrap();
+    };
+
+    let root = match name {
+        Some(name) => {
+            if Path::new(name).exists() {
+                return wrong(emitter, &format!("path '{}' already exists", name));
+            }
+
+            PathBuf::from(name)
+        }
+
+        None => env::current_dir().unwrap_or_default(),
+    };
+
+    let project = root
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match template.write(&root, &project) {
+        Ok(skipped) => {
+            for path in skipped {
+                weird(emitter, &format!("left '{}' as it was", path.display()))
+            }
+
+            println!(
+                "{} `{}` from template `{}`",
+                "Created".green().bold(),
+                project,
+                template_name
+            )
+        }
+
+        Err(why) => wrong(emitter, &format!("couldn't create project :: {}", why)),
+    }
+}
+
+// Installs dependencies at their locked commits, `offline` only using what's already on disk
+pub fn get(offline: bool, emitter: &mut dyn Emitter) -> bool {
+    sync(&|_| false, None, offline, emitter).is_some()
+}
+
+// Moves the lock of `name`, or of every dependency, forward to the latest commit
+pub fn update(name: Option<&str>, emitter: &mut dyn Emitter) -> bool {
+    match name {
+        Some(name) => in_workspace(emitter, |emitter| update_here(name, emitter)),
+        None => sync(&|_| true, None, false, emitter).is_some(),
+    }
+}
+
+fn update_here(name: &str, emitter: &mut dyn Emitter) -> bool {
+    // without a manifest, syncing says what is missing
+    if Path::new("wu.toml").exists() && !in_graph(name) {
+        wrong(
+            emitter,
+            &format!(
+                "Couldn't find dependency `{}` in 'wu.toml' or '{}'",
+                name, LOCK_FILE
+            ),
+        );
+        return false;
+    }
+
+    sync_here(&|dependency| dependency == name, None, false, emitter).is_some()
+}
+
+// Whether `name` is required by the project or its members, or was locked as something they need
+fn in_graph(name: &str) -> bool {
+    if Lock::read(LOCK_FILE).get(name).is_some() {
+        return true;
+    }
+
+    let manifest = match project() {
+        Some(manifest) => manifest,
+        None => return false,
+    };
+
+    let mut manifests = vec![manifest.clone()];
+
+    if let Some(workspace) = manifest.workspace {
+        for member in workspace.members.iter() {
+            let path = Path::new(member).join("wu.toml");
+
+            if let Ok(contents) = fs::read_to_string(&path) {
+                if let (Some(member_manifest), _) =
+                    Manifest::parse(&path.display().to_string(), &contents)
+                {
+                    manifests.push(member_manifest)
+                }
+            }
+        }
+    }
+
+    manifests.iter().any(|manifest| {
+        manifest
+            .dependencies
+            .iter()
+            .any(|dependency| dependency.name == name)
+    })
+}
+
+// Copies every resolved dependency into `vendor/`, which `--offline` falls back to
+pub fn vendor(emitter: &mut dyn Emitter) -> bool {
+    in_workspace(emitter, vendor_here)
+}
+
+fn vendor_here(emitter: &mut dyn Emitter) -> bool {
+    let (dependencies, dep_path) = match sync_here(&|_| false, None, false, emitter) {
+        Some(resolved) => resolved,
+        None => {
+            wrong(
+                emitter,
+                "Couldn't vendor dependencies that failed to install",
+            );
+            return false;
+        }
+    };
+
+    if !clear(VENDOR_DIR, emitter) {
+        return false;
+    }
+
+    if let Err(why) = fs::create_dir_all(VENDOR_DIR) {
+        wrong(
+            emitter,
+            &format!("Failed to create '{}' :: {}", VENDOR_DIR, why),
+        );
+        return false;
+    }
+
+    let mut success = true;
+
+    for dependency in dependencies.iter() {
+        emitter.status(&format!(
+            "{} {} => `{}/`",
+            "Vendoring".green().bold(),
+            dependency.name,
+            VENDOR_DIR
+        ));
+
+        let from = format!("{}{}", dep_path, dependency.name);
+        let to = format!("{}/{}", VENDOR_DIR, dependency.name);
+
+        if let Err(why) = copy_dir(&from, &to) {
+            wrong(emitter, &format!("Failed to vendor '{}' :: {}", from, why));
+            success = false
+        }
+    }
+
+    // what was vendored, so `--offline` can tell whether it's still what's locked
+    if Path::new(LOCK_FILE).exists() {
+        if let Err(why) = fs::copy(LOCK_FILE, VENDOR_LOCK) {
+            wrong(
+                emitter,
+                &format!("Failed to write '{}' :: {}", VENDOR_LOCK, why),
+            );
+            success = false
+        }
+    }
+
+    success
+}
+
+// Adds `user/repo` to the manifest's dependencies and installs it, named after the repo by default
+pub fn add(repo: &str, name: Option<&str>, tag: Option<&str>, emitter: &mut dyn Emitter) -> bool {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => repo
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':'])
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    };
+
+    if name.is_empty() {
+        wrong(
+            emitter,
+            &format!("Couldn't name '{}', try `--name <name>`", repo),
+        );
+        return false;
+    }
+
+    let repo_value = Value::String(repo.to_string());
+
+    let value = match tag {
+        Some(tag) => format!(
+            "{{ git = {}, tag = {} }}",
+            repo_value,
+            Value::String(tag.to_string())
+        ),
+
+        None => repo_value.to_string(),
+    };
+
+    let contents = match fs::read_to_string("wu.toml") {
+        Ok(contents) => contents,
+        Err(_) => {
+            wrong(emitter, "Couldn't find 'wu.toml'");
+            return false;
+        }
+    };
+
+    let edited = manifest::add_dependency(&contents, &name, &value);
+
+    // an edit that breaks the manifest is not written
+    if let (None, diagnostics) = Manifest::parse("wu.toml", &edited) {
+        for diagnostic in diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_wrong())
+        {
+            emitter.emit(diagnostic)
+        }
+
+        wrong(emitter, &format!("Couldn't add `{}` to 'wu.toml'", name));
+        return false;
+    }
+
+    if let Err(why) = fs::write("wu.toml", edited) {
+        wrong(emitter, &format!("Failed to write 'wu.toml' :: {}", why));
+        return false;
+    }
+
+    emitter.status(&format!(
+        "{} {} to `wu.toml`",
+        "Adding".green().bold(),
+        name
+    ));
+
+    if sync(
+        &|dependency| dependency == name,
+        Some(&name),
+        false,
+        emitter,
+    )
+    .is_some()
+    {
+        return true;
+    }
+
+    // a dependency that doesn't fit the rest of the graph is not kept
+    if let Err(why) = fs::write("wu.toml", contents) {
+        wrong(emitter, &format!("Failed to write 'wu.toml' :: {}", why))
+    }
+
+    wrong(
+        emitter,
+        &format!("Couldn't add `{}`, 'wu.toml' is left as it was", name),
+    );
+
+    false
+}
+
+// Takes `name` out of the manifest's dependencies, its checkout and the lock
+pub fn remove(name: &str, emitter: &mut dyn Emitter) -> bool {
+    let contents = match fs::read_to_string("wu.toml") {
+        Ok(contents) => contents,
+        Err(_) => {
+            wrong(emitter, "Couldn't find 'wu.toml'");
+            return false;
+        }
+    };
+
+    let contents = match manifest::remove_dependency(&contents, name) {
+        Some(contents) => contents,
+        None => {
+            wrong(
+                emitter,
+                &format!("Couldn't find dependency `{}` in 'wu.toml'", name),
+            );
+            return false;
+        }
+    };
 
-        File::create("src/init.wu").unwrap();
+    if let Err(why) = fs::write("wu.toml", &contents) {
+        wrong(emitter, &format!("Failed to write 'wu.toml' :: {}", why));
+        return false;
     }
+
+    emitter.status(&format!(
+        "{} {} from `wu.toml`",
+        "Removing".green().bold(),
+        name
+    ));
+
+    let dep_path = match Manifest::parse("wu.toml", &contents) {
+        (Some(manifest), _) => manifest.libpath,
+        (None, _) => "libs/".to_string(),
+    };
+
+    let path = format!("{}{}", dep_path, name);
+
+    if !has_changes(&path, emitter) && !clear(&path, emitter) {
+        return false;
+    }
+
+    let mut lock = Lock::read(LOCK_FILE);
+
+    if lock.packages.remove(name).is_some() {
+        if let Err(why) = lock.write(LOCK_FILE) {
+            wrong(
+                emitter,
+                &format!("Failed to write '{}' :: {}", LOCK_FILE, why),
+            );
+            return false;
+        }
+    }
+
+    // what only `name` needed goes with it
+    in_workspace(emitter, prune_here)
 }
 
-pub fn get() {
-    if Path::new("wu.toml").exists() {
-        let mut config = File::open("wu.toml").unwrap();
+// Resolves the graph again, taking whatever it no longer reaches out of the libpath
+fn prune_here(emitter: &mut dyn Emitter) -> bool {
+    let (dependencies, dep_path) = match sync_here(&|_| false, None, false, emitter) {
+        Some(resolved) => resolved,
+        None => return false,
+    };
 
-        let mut contents = String::new();
-        config.read_to_string(&mut contents).unwrap();
+    let entries = match fs::read_dir(&dep_path) {
+        Ok(entries) => entries,
+        Err(_) => return true,
+    };
 
-        match toml::from_str::<Value>(&contents) {
-            Ok(value) => match value.get("dependencies") {
-                Some(depends) => match *depends {
-                    Value::Table(ref t) => {
-                        let mut modules = Vec::new();
+    let mut success = true;
 
-                        let mut dep_path = "libs/".to_string();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
 
-                        if let Some(ref path) = value.get("libpath") {
-                            if let Value::String(ref path) = path {
-                                dep_path = format!("{}", path);
-                            } else {
-                                wrong("Expected string `libpath` value")
-                            }
-                        }
+        if name.starts_with('.')
+            || dependencies
+                .iter()
+                .any(|dependency| dependency.name == name)
+        {
+            continue;
+        }
+
+        let path = format!("{}{}", dep_path, name);
+
+        if has_changes(&path, emitter) {
+            continue;
+        }
+
+        emitter.status(&format!(
+            "{} {} from `{}`",
+            "Pruning".green().bold(),
+            name,
+            dep_path
+        ));
+
+        success &= clear(&path, emitter)
+    }
 
+    success
+}
+
+// Prints every cached remote, how much room it takes and the commits projects checked out of it
+pub fn cache_list(emitter: &mut dyn Emitter) -> bool {
+    let root = match cache::root() {
+        Some(root) => root,
+        None => {
+            wrong(emitter, "Couldn't find the cache, `$WU_HOME` is not set");
+            return false;
+        }
+    };
+
+    let mirrors = cache::mirrors(&root);
+
+    if mirrors.is_empty() {
+        println!("Nothing cached in `{}`", root.display());
+        return true;
+    }
 
-                        for member in t {
-                            if !Path::new(&dep_path).exists() {
-                                fs::create_dir_all(&dep_path).unwrap();
-                            }
+    let mut total = 0;
 
-                            if let Value::String(ref url) = *member.1 {
-                                let path = &format!("{}{}", dep_path, member.0);
+    for mirror in mirrors.iter() {
+        let size = fs_extra::dir::get_size(&mirror.path).unwrap_or(0);
 
-                                if Path::new(path).exists() {
-                                    fs::remove_dir_all(path).unwrap()
-                                }
+        total += size;
+
+        println!("{} ({})", mirror.url.bold(), human_size(size));
+
+        for commit in mirror.commits() {
+            println!("    {}", commit)
+        }
+    }
+
+    println!(
+        "{} {} in `{}`",
+        "Cached".green().bold(),
+        human_size(total),
+        root.display()
+    );
+
+    true
+}
+
+// Empties the cache, or just the remotes whose URL contains `filter`
+pub fn cache_clean(filter: Option<&str>, emitter: &mut dyn Emitter) -> bool {
+    let root = match cache::root() {
+        Some(root) => root,
+        None => {
+            wrong(emitter, "Couldn't find the cache, `$WU_HOME` is not set");
+            return false;
+        }
+    };
+
+    let mut freed = 0;
+    let mut success = true;
+
+    for mirror in cache::mirrors(&root) {
+        if filter.is_some_and(|filter| !mirror.url.contains(filter)) {
+            continue;
+        }
+
+        freed += fs_extra::dir::get_size(&mirror.path).unwrap_or(0);
+
+        println!("{} {}", "Removing".green().bold(), mirror.url);
+
+        if let Err(why) = fs::remove_dir_all(&mirror.path) {
+            wrong(
+                emitter,
+                &format!("Failed to remove '{}' :: {}", mirror.path.display(), why),
+            );
+
+            success = false
+        }
+    }
+
+    println!("{} {}", "Freed".green().bold(), human_size(freed));
+
+    success
+}
+
+fn human_size(bytes: u64) -> String {
+    let mut size = bytes as f64;
+
+    for unit in ["B", "KiB", "MiB"].iter() {
+        if size < 1024.0 {
+            return format!("{:.1} {}", size, unit);
+        }
+
+        size /= 1024.0
+    }
+
+    format!("{:.1} GiB", size)
+}
+
+// Installs the dependency graph, or just the part of it under `only`,
+// at locked commits unless `unlock` says otherwise. Hands back what got installed
+// and where, unless something failed
+fn sync(
+    unlock: &dyn Fn(&str) -> bool,
+    only: Option<&str>,
+    offline: bool,
+    emitter: &mut dyn Emitter,
+) -> Option<(Vec<Dependency>, String)> {
+    in_workspace(emitter, |emitter| sync_here(unlock, only, offline, emitter))
+}
+
+fn sync_here(
+    unlock: &dyn Fn(&str) -> bool,
+    only: Option<&str>,
+    offline: bool,
+    emitter: &mut dyn Emitter,
+) -> Option<(Vec<Dependency>, String)> {
+    if !Path::new("wu.toml").exists() {
+        wrong(emitter, "Couldn't find 'wu.toml'");
+        return None;
+    }
+
+    let manifest = Manifest::read("wu.toml", emitter)?;
+
+    let mut members = Vec::new();
+
+    if let Some(ref workspace) = manifest.workspace {
+        for member in workspace.members.iter() {
+            let path = PathBuf::from(member);
+
+            members.push(Member {
+                name: format!("{}/", member.trim_end_matches('/')),
+                manifest: Manifest::read(&path.join("wu.toml").display().to_string(), emitter)?,
+                path,
+            })
+        }
+    }
+
+    let mut resolver = Resolver::new(manifest.libpath.clone(), unlock, only, offline, emitter);
+
+    resolver.resolve(&manifest, &members);
+    resolver.link_members(&members);
+    resolver.finish();
+
+    if resolver.failed {
+        return None;
+    }
+
+    let mut dependencies = resolver.resolved.into_values().collect::<Vec<_>>();
+
+    dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Some((dependencies, resolver.dep_path))
+}
+
+// A package of the workspace, installed along with the root
+struct Member {
+    // its directory with a trailing `/`, which no dependency is named like
+    name: String,
+    path: PathBuf,
+    manifest: Manifest,
+}
+
+// Runs `f` at the root of the workspace the current directory is a member of, if any
+fn in_workspace<T>(emitter: &mut dyn Emitter, f: impl FnOnce(&mut dyn Emitter) -> T) -> T {
+    let back = enter_workspace(emitter);
+
+    let result = f(emitter);
+
+    if let Some(back) = back {
+        if let Err(why) = env::set_current_dir(&back) {
+            wrong(
+                emitter,
+                &format!("Failed to return to '{}' :: {}", back.display(), why),
+            )
+        }
+    }
+
+    result
+}
+
+// Moves to the workspace root, handing back where it came from
+fn enter_workspace(emitter: &dyn Emitter) -> Option<PathBuf> {
+    let here = env::current_dir().ok()?.canonicalize().ok()?;
+
+    for root in here.ancestors().skip(1) {
+        let path = root.join("wu.toml");
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        let workspace = match Manifest::parse(&path.display().to_string(), &contents) {
+            (Some(manifest), _) => manifest.workspace,
+            (None, _) => None,
+        };
+
+        let is_member = workspace.is_some_and(|workspace| {
+            workspace
+                .members
+                .iter()
+                .any(|member| root.join(member).canonicalize().ok().as_ref() == Some(&here))
+        });
+
+        if is_member {
+            emitter.status(&format!(
+                "{} workspace `{}`",
+                "Entering".green().bold(),
+                root.display()
+            ));
+
+            env::set_current_dir(root).ok()?;
+
+            return Some(here);
+        }
+    }
+
+    None
+}
+
+// Walks the dependencies of dependencies, installing each name once
+struct Resolver<'a> {
+    dep_path: String,
+    unlock: &'a dyn Fn(&str) -> bool,
+    // the one dependency to install along with what it requires, the rest of
+    // the graph is only read from disk to check it agrees
+    only: Option<&'a str>,
+    // only installing from the libpath, `vendor/` or the cache
+    offline: bool,
+    failed: bool,
+    emitter: &'a mut dyn Emitter,
+
+    lock: Lock,
+    new_lock: Lock,
+
+    resolved: HashMap<String, Dependency>,
+    // what was installed rather than read from disk
+    installed: HashSet<String>,
+    // who asked for each name first, `None` being the project itself
+    required_by: HashMap<String, Option<String>>,
+    // edges of the resolved tree, keyed by parent
+    children: HashMap<Option<String>, Vec<String>>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(
+        dep_path: String,
+        unlock: &'a dyn Fn(&str) -> bool,
+        only: Option<&'a str>,
+        offline: bool,
+        emitter: &'a mut dyn Emitter,
+    ) -> Self {
+        let lock = Lock::read(LOCK_FILE);
+
+        let new_lock = match only {
+            Some(_) => lock.clone(),
+            None => Lock::default(),
+        };
+
+        Resolver {
+            dep_path,
+            unlock,
+            only,
+            offline,
+            failed: false,
+            emitter,
+
+            lock,
+            new_lock,
+
+            resolved: HashMap::new(),
+            installed: HashSet::new(),
+            required_by: HashMap::new(),
+            children: HashMap::new(),
+        }
+    }
+
+    fn resolve(&mut self, manifest: &Manifest, members: &[Member]) {
+        let mut pending = VecDeque::new();
+
+        let mut roots = vec![(None, self.dependencies(manifest, Path::new("")))];
+
+        // members are where their dependencies hang from
+        for member in members.iter() {
+            self.children
+                .entry(None)
+                .or_default()
+                .push(member.name.clone());
+
+            roots.push((
+                Some(member.name.clone()),
+                self.dependencies(&member.manifest, &member.path),
+            ))
+        }
+
+        for (parent, dependencies) in roots {
+            for dependency in dependencies {
+                pending.push_back((parent.clone(), dependency))
+            }
+        }
+
+        while let Some((parent, dependency)) = pending.pop_front() {
+            let name = dependency.name.clone();
+
+            if let Some(existing) = self.resolved.get(&name) {
+                if existing.origin == dependency.origin {
+                    self.children.entry(parent).or_default().push(name);
+                } else {
+                    self.failed = true;
+
+                    let first = &self.required_by[&name];
+
+                    wrong(
+                        self.emitter,
+                        &format!(
+                            "Conflicting requirements for `{}`, {} wants {} while {} wants {}",
+                            name,
+                            describe(first),
+                            existing.origin,
+                            describe(&parent),
+                            dependency.origin
+                        ),
+                    )
+                }
+
+                continue;
+            }
+
+            self.children
+                .entry(parent.clone())
+                .or_default()
+                .push(name.clone());
+            let folder = format!("{}{}", self.dep_path, name);
+
+            let wanted = match self.only {
+                Some(only) => {
+                    name == only
+                        || parent
+                            .as_ref()
+                            .is_some_and(|parent| self.installed.contains(parent))
+                        || !Path::new(&folder).exists()
+                }
+
+                None => true,
+            };
+
+            self.required_by.insert(name.clone(), parent);
+
+            if wanted {
+                self.installed.insert(name.clone());
+                self.install(&dependency);
+            }
+            let path = Path::new(&folder).join("wu.toml");
+
+            let child_manifest = if path.exists() {
+                let child_manifest = Manifest::read(&path.display().to_string(), self.emitter);
+
+                self.failed |= child_manifest.is_none();
+
+                child_manifest
+            } else {
+                None
+            };
+
+            if let Some(child_manifest) = child_manifest {
+                let base = match dependency.origin {
+                    Origin::Path(ref source) => PathBuf::from(source),
+                    Origin::Git(..) => PathBuf::from(&folder),
+                };
+
+                for child in self.dependencies(&child_manifest, &base) {
+                    pending.push_back((Some(name.clone()), child))
+                }
+            }
+
+            self.resolved.insert(name, dependency);
+        }
+    }
 
-                                println!(
-                                    "{}",
-                                    format!(
-                                        "{} {} => `{}`",
-                                        "Cloning".green().bold(),
-                                        member.0,
-                                        dep_path
-                                    )
-                                );
-                                clone(&format!("https://github.com/{}", url), path);
+    // The dependencies of a manifest, with paths made relative to `base`
+    fn dependencies(&self, manifest: &Manifest, base: &Path) -> Vec<Dependency> {
+        let mut dependencies = manifest.dependencies.clone();
 
-                                modules.push(format!("import {}", member.0))
-                            } else {
-                                wrong("Expected string URL value")
-                            }
-                        }
+        for dependency in dependencies.iter_mut() {
+            if let Origin::Path(ref mut source) = dependency.origin {
+                let path = base.join(&source);
+
+                *source = match fs::canonicalize(&path) {
+                    Ok(path) => path.display().to_string(),
+                    Err(_) => path.display().to_string(),
+                }
+            }
+        }
+
+        dependencies
+    }
+
+    fn install(&mut self, dependency: &Dependency) {
+        if let Err(why) = fs::create_dir_all(&self.dep_path) {
+            wrong(
+                self.emitter,
+                &format!("Failed to create '{}' :: {}", self.dep_path, why),
+            );
+            self.failed = true;
+
+            return self.keep_lock(&dependency.name);
+        }
+
+        let path = &format!("{}{}", self.dep_path, dependency.name);
+
+        let (url, reference) = match dependency.origin {
+            Origin::Git(ref url, ref reference) => (url, reference),
+
+            Origin::Path(ref source) => {
+                let vendored = format!("{}/{}", VENDOR_DIR, dependency.name);
+
+                if has_changes(path, self.emitter) {
+                    return self.keep_lock(&dependency.name);
+                }
+
+                if !clear(path, self.emitter) {
+                    self.failed = true;
+
+                    return self.keep_lock(&dependency.name);
+                }
+
+                // a build machine may have the vendored copy but not the directory itself
+                if self.offline && !Path::new(source).exists() && Path::new(&vendored).is_dir() {
+                    self.emitter.status(&format!(
+                        "{} {} => `{}` ({})",
+                        "Using".green().bold(),
+                        dependency.name,
+                        self.dep_path,
+                        VENDOR_DIR
+                    ));
+
+                    if let Err(why) = copy_dir(&vendored, path) {
+                        wrong(
+                            self.emitter,
+                            &format!("Failed to copy '{}' :: {}", vendored, why),
+                        );
+                        self.failed = true
+                    }
+
+                    return;
+                }
+
+                self.emitter.status(&format!(
+                    "{} {} => `{}`",
+                    "Linking".green().bold(),
+                    dependency.name,
+                    self.dep_path
+                ));
+
+                self.failed |= !link(source, path, self.emitter);
+
+                return;
+            }
+        };
+
+        // a lock only holds for what it was made for
+        let locked = self
+            .lock
+            .get(&dependency.name)
+            .filter(|locked| {
+                locked.url == *url
+                    && locked.reference == *reference
+                    && !(self.unlock)(&dependency.name)
+            })
+            .cloned();
+
+        if self.offline {
+            match self.install_offline(&dependency.name, path, url, locked.as_ref()) {
+                Some(commit) => self.lock_commit(&dependency.name, url, reference, commit),
+
+                None => {
+                    self.failed = true;
+                    self.keep_lock(&dependency.name)
+                }
+            }
+
+            return;
+        }
+
+        // remotes are only talked to through the cache, checkouts come out of it
+        let mirror = self.mirror(url, locked.as_ref());
+
+        let source = match mirror {
+            Some(ref mirror) => mirror.path.display().to_string(),
+            None => url.clone(),
+        };
+
+        let repo = match open(path, url) {
+            Some(repo) => {
+                if has_changes(path, self.emitter) {
+                    return self.keep_lock(&dependency.name);
+                }
+
+                self.emitter.status(&format!(
+                    "{} {} => `{}` ({})",
+                    "Fetching".green().bold(),
+                    dependency.name,
+                    self.dep_path,
+                    reference
+                ));
+
+                // nothing new is needed when the locked commit is already around
+                let has_locked = locked
+                    .as_ref()
+                    .is_some_and(|locked| repo.revparse_single(&locked.commit).is_ok());
+
+                if !has_locked {
+                    fetch(&repo, &source, self.emitter);
+
+                    // the remote's default branch may have moved
+                    if let Some(head) = mirror.as_ref().and_then(Mirror::head) {
+                        let target = head.replacen("refs/heads/", "refs/remotes/origin/", 1);
+
+                        let _ = repo.reference_symbolic(
+                            "refs/remotes/origin/HEAD",
+                            &target,
+                            true,
+                            "wu sync",
+                        );
+                    }
+                }
+
+                Some(repo)
+            }
+
+            None => {
+                // a checkout of another remote is replaced, unless it was worked on
+                if has_changes(path, self.emitter) {
+                    return self.keep_lock(&dependency.name);
+                }
+
+                if !clear(path, self.emitter) {
+                    self.failed = true;
+
+                    return self.keep_lock(&dependency.name);
+                }
+
+                self.emitter.status(&format!(
+                    "{} {} => `{}` ({})",
+                    "Cloning".green().bold(),
+                    dependency.name,
+                    self.dep_path,
+                    reference
+                ));
+
+                let repo = clone(&source, path, self.emitter);
+
+                // so the checkout is recognized as coming from `url` next time
+                if let Some(ref repo) = repo {
+                    if let Err(why) = repo.remote_set_url("origin", url) {
+                        wrong(
+                            self.emitter,
+                            &format!("Failed to set origin of '{}' :: {}", path, why),
+                        )
                     }
+                }
 
-                    _ => wrong(r#"Expected key e.g. `a = "b"`"#),
-                },
-                _ => (),
+                repo
+            }
+        };
+
+        let commit = match repo {
+            Some(repo) => match locked {
+                Some(locked) => checkout(&repo, &locked.commit, self.emitter),
+                None => checkout(&repo, &reference.spec(), self.emitter),
             },
 
-            Err(_) => wrong("Something went wrong in 'wu.toml'"),
+            None => None,
+        };
+
+        match commit {
+            Some(commit) => {
+                if let Some(ref mirror) = mirror {
+                    mirror.record(&commit)
+                }
+
+                self.lock_commit(&dependency.name, url, reference, commit)
+            }
+
+            // failing to fetch is no reason to forget the lock
+            None => {
+                self.failed = true;
+                self.keep_lock(&dependency.name)
+            }
+        }
+    }
+
+    // Installs the locked commit from what's on disk: the libpath, `vendor/` or the cache
+    fn install_offline(
+        &mut self,
+        name: &str,
+        path: &str,
+        url: &str,
+        locked: Option<&Locked>,
+    ) -> Option<String> {
+        let commit = match locked {
+            Some(locked) => locked.commit.clone(),
+            None => {
+                wrong(
+                    self.emitter,
+                    &format!(
+                        "Couldn't resolve `{}` offline, it's not locked in '{}'",
+                        name, LOCK_FILE
+                    ),
+                );
+
+                return None;
+            }
+        };
+
+        let using = |emitter: &dyn Emitter, from: &str| {
+            emitter.status(&format!(
+                "{} {} => `{}` ({})",
+                "Using".green().bold(),
+                name,
+                self.dep_path,
+                from
+            ))
+        };
+
+        if let Some(repo) = open(path, url) {
+            let head = repo
+                .head()
+                .ok()
+                .and_then(|head| head.target())
+                .map(|id| id.to_string());
+
+            if head.as_deref() == Some(commit.as_str()) {
+                using(self.emitter, "checked out");
+
+                return Some(commit);
+            }
+
+            if !is_dirty(&repo) && repo.revparse_single(&commit).is_ok() {
+                using(self.emitter, "checked out");
+
+                return checkout(&repo, &commit, self.emitter);
+            }
+        }
+
+        let vendored = format!("{}/{}", VENDOR_DIR, name);
+
+        let is_vendored = Path::new(&vendored).is_dir()
+            && Lock::read(VENDOR_LOCK)
+                .get(name)
+                .is_some_and(|locked| locked.url == url && locked.commit == commit);
+
+        if is_vendored {
+            if has_changes(path, self.emitter) {
+                return None;
+            }
+
+            using(self.emitter, VENDOR_DIR);
+
+            if !clear(path, self.emitter) {
+                return None;
+            }
+
+            return match copy_dir(&vendored, path) {
+                Ok(()) => Some(commit),
+                Err(why) => {
+                    wrong(
+                        self.emitter,
+                        &format!("Failed to copy '{}' :: {}", vendored, why),
+                    );
+                    None
+                }
+            };
+        }
+
+        let mirror = cache::root().and_then(|root| Mirror::find(&root, url));
+
+        if let Some(mirror) = mirror.filter(|mirror| mirror.has(&commit)) {
+            if has_changes(path, self.emitter) {
+                return None;
+            }
+
+            using(self.emitter, "cache");
+
+            if !clear(path, self.emitter) {
+                return None;
+            }
+
+            let repo = clone(&mirror.path.display().to_string(), path, self.emitter)?;
+
+            if let Err(why) = repo.remote_set_url("origin", url) {
+                wrong(
+                    self.emitter,
+                    &format!("Failed to set origin of '{}' :: {}", path, why),
+                )
+            }
+
+            return checkout(&repo, &commit, self.emitter);
+        }
+
+        wrong(
+            self.emitter,
+            &format!(
+                "Couldn't find `{}` at {} offline, it's not in `{}`, `{}/` or the cache",
+                name, commit, self.dep_path, VENDOR_DIR
+            ),
+        );
+
+        None
+    }
+
+    fn lock_commit(&mut self, name: &str, url: &str, reference: &Reference, commit: String) {
+        self.new_lock.packages.insert(
+            name.to_string(),
+            Locked {
+                url: url.to_string(),
+                reference: reference.clone(),
+                commit,
+            },
+        );
+    }
+
+    // The cache's mirror of `url`, downloading into it unless the locked commit is already there
+    fn mirror(&mut self, url: &str, locked: Option<&Locked>) -> Option<Mirror> {
+        let root = cache::root()?;
+
+        let mirror = match Mirror::open(&root, url) {
+            Ok(mirror) => mirror,
+            Err(why) => {
+                wrong(
+                    self.emitter,
+                    &format!("Failed to cache '{}' :: {}", url, why),
+                );
+                return None;
+            }
+        };
+
+        if locked.is_some_and(|locked| mirror.has(&locked.commit)) {
+            return Some(mirror);
+        }
+
+        self.emitter
+            .status(&format!("{} {}", "Downloading".green().bold(), url));
+
+        if let Err(why) = mirror.fetch() {
+            wrong(
+                self.emitter,
+                &format!("Failed to download '{}' :: {}", url, why),
+            )
+        }
+
+        Some(mirror)
+    }
+
+    fn keep_lock(&mut self, name: &str) {
+        if let Some(old) = self.lock.get(name) {
+            self.new_lock.packages.insert(name.to_string(), old.clone());
+        }
+    }
+
+    // Points the libpath of each member at the shared one, so their imports find the same things
+    fn link_members(&mut self, members: &[Member]) {
+        if members.is_empty() {
+            return;
+        }
+
+        if let Err(why) = fs::create_dir_all(&self.dep_path) {
+            wrong(
+                self.emitter,
+                &format!("Failed to create '{}' :: {}", self.dep_path, why),
+            );
+            self.failed = true;
+
+            return;
+        }
+
+        let shared = match fs::canonicalize(&self.dep_path) {
+            Ok(shared) => shared,
+            Err(_) => return,
+        };
+
+        for member in members.iter() {
+            let path = member
+                .path
+                .join(member.manifest.libpath.trim_end_matches('/'));
+
+            if fs::canonicalize(&path).ok().as_ref() == Some(&shared) {
+                continue;
+            }
+
+            let is_link = match fs::symlink_metadata(&path) {
+                Ok(meta) => meta.file_type().is_symlink(),
+                Err(_) => true,
+            };
+
+            let path = path.display().to_string();
+
+            if !is_link {
+                weird(
+                    self.emitter,
+                    &format!(
+                        "Leaving `{}` as is, remove it to share the workspace's `{}`",
+                        path, self.dep_path
+                    ),
+                );
+
+                continue;
+            }
+
+            if !clear(&path, self.emitter) {
+                self.failed = true;
+
+                continue;
+            }
+
+            self.failed |= !link(&shared.display().to_string(), &path, self.emitter);
+        }
+    }
+
+    fn finish(&mut self) {
+        // a graph that didn't resolve has nothing worth locking
+        if !self.failed && self.new_lock != self.lock {
+            if let Err(why) = self.new_lock.write(LOCK_FILE) {
+                wrong(
+                    self.emitter,
+                    &format!("Failed to write '{}' :: {}", LOCK_FILE, why),
+                )
+            }
+        }
+
+        // half a tree would read like everything went fine
+        if !self.failed && !self.children.is_empty() {
+            self.emitter.status(&"Resolved".green().bold().to_string());
+
+            self.print_tree(&None, "    ", &mut HashSet::new())
+        }
+    }
+
+    fn print_tree(&self, parent: &Option<String>, indent: &str, printed: &mut HashSet<String>) {
+        let children = match self.children.get(parent) {
+            Some(children) => children,
+            None => return,
+        };
+
+        for (i, name) in children.iter().enumerate() {
+            let last = i == children.len() - 1;
+
+            let (branch, next) = match (parent.is_some(), last) {
+                (false, _) => ("", indent.to_string()),
+                (true, false) => ("├─ ", format!("{}│  ", indent)),
+                (true, true) => ("└─ ", format!("{}   ", indent)),
+            };
+
+            let origin = match self.resolved.get(name) {
+                Some(dependency) => dependency.origin.to_string(),
+                None => "(workspace member)".to_string(),
+            };
+
+            // shared dependencies are only spelled out once
+            if !printed.insert(name.clone()) {
+                self.emitter
+                    .status(&format!("{}{}{} (*)", indent, branch, name));
+
+                continue;
+            }
+
+            self.emitter
+                .status(&format!("{}{}{} {}", indent, branch, name, origin));
+
+            self.print_tree(&Some(name.clone()), &next, printed)
+        }
+    }
+}
+
+fn describe(parent: &Option<String>) -> String {
+    match *parent {
+        Some(ref name) => format!("`{}`", name),
+        None => "the project".to_string(),
+    }
+}
+
+// `[build] out_dir` from the project's wu.toml, if there is one
+pub fn out_dir() -> Option<String> {
+    project()?.out_dir
+}
+
+// `[package] entry` from the project's wu.toml, if there is one
+pub fn entry() -> Option<String> {
+    project()?.package?.entry
+}
+
+// What `wu build` compiles at a workspace root, and the directories to leave out while at it:
+// each member, the shared libpath once rather than through every member's link to it,
+// and the root itself when it's a package too. Left out directories are canonical,
+// as members and the libpath link to each other
+pub fn workspace_build() -> Option<(Vec<String>, Vec<String>)> {
+    let manifest = project()?;
+    let workspace = manifest.workspace?;
+
+    let shared = manifest.libpath.trim_end_matches('/').to_string();
+
+    let mut targets = workspace.members.clone();
+    let mut skipped = workspace.members.clone();
+
+    for member in workspace.members.iter() {
+        let path = Path::new(member).join("wu.toml");
+
+        if let Ok(contents) = fs::read_to_string(&path) {
+            if let (Some(member_manifest), _) =
+                Manifest::parse(&path.display().to_string(), &contents)
+            {
+                let libpath = Path::new(member).join(member_manifest.libpath.trim_end_matches('/'));
+
+                skipped.push(libpath.display().to_string())
+            }
+        }
+    }
+
+    if Path::new(&shared).is_dir() {
+        targets.push(shared.clone())
+    }
+
+    skipped.push(shared);
+
+    if manifest.package.is_some() {
+        targets.push(".".to_string())
+    }
+
+    let skipped = skipped
+        .iter()
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .map(|path| path.display().to_string())
+        .collect();
+
+    Some((targets, skipped))
+}
+
+// The project's manifest, quietly, commands that need it report what's wrong
+fn project() -> Option<Manifest> {
+    let contents = fs::read_to_string("wu.toml").ok()?;
+
+    Manifest::parse("wu.toml", &contents).0
+}
+
+// Clears whatever was synced to `path` before, not following links
+fn clear(path: &str, emitter: &mut dyn Emitter) -> bool {
+    let removed = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => return true,
+    };
+
+    if let Err(why) = removed {
+        wrong(emitter, &format!("Failed to remove '{}' :: {}", path, why));
+        return false;
+    }
+
+    true
+}
+
+// Links a path dependency into the libpath, copying it where links aren't available
+fn link(source: &str, path: &str, emitter: &mut dyn Emitter) -> bool {
+    let target = match fs::canonicalize(source) {
+        Ok(target) => target,
+        Err(why) => {
+            wrong(emitter, &format!("Failed to find '{}' :: {}", source, why));
+            return false;
         }
+    };
+
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&target, path).map_err(|why| why.to_string());
+
+    #[cfg(not(unix))]
+    let result = fs::create_dir_all(path)
+        .map_err(|why| why.to_string())
+        .and_then(|_| {
+            let mut options = fs_extra::dir::CopyOptions::new();
+            options.content_only = true;
+
+            fs_extra::dir::copy(&target, path, &options).map_err(|why| why.to_string())
+        })
+        .map(|_| ());
+
+    if let Err(why) = result {
+        wrong(emitter, &format!("Failed to link '{}' :: {}", source, why));
+        return false;
+    }
+
+    true
+}
+
+// Copies the files of a checkout or linked directory, leaving its git history behind
+fn copy_dir(from: &str, to: &str) -> Result<(), String> {
+    let from = fs::canonicalize(from).map_err(|why| why.to_string())?;
+
+    fs::create_dir_all(to).map_err(|why| why.to_string())?;
+
+    let mut options = fs_extra::dir::CopyOptions::new();
+    options.content_only = true;
+
+    fs_extra::dir::copy(&from, to, &options).map_err(|why| why.to_string())?;
+
+    let git = Path::new(to).join(".git");
+
+    if git.exists() {
+        fs::remove_dir_all(git).map_err(|why| why.to_string())?
+    }
+
+    Ok(())
+}
+
+// An earlier checkout of `url` at `path`, if there is one
+fn open(path: &str, url: &str) -> Option<Repository> {
+    // a link belongs to a path dependency
+    if fs::symlink_metadata(path).ok()?.file_type().is_symlink() {
+        return None;
+    }
+
+    let repo = Repository::open(path).ok()?;
+
+    let same_url = repo
+        .find_remote("origin")
+        .ok()
+        .is_some_and(|remote| remote.url() == Some(url));
+
+    if same_url {
+        Some(repo)
     } else {
-        wrong("Couldn't find 'wu.toml'");
+        None
     }
 }
 
-fn clone(url: &str, path: &str) {
+// Whether `path` is a checkout with local changes, which are never cleared away
+fn has_changes(path: &str, emitter: &mut dyn Emitter) -> bool {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink());
+
+    let changed = !is_link && Repository::open(path).is_ok_and(|repo| is_dirty(&repo));
+
+    if changed {
+        weird(
+            emitter,
+            &format!(
+                "Leaving `{}` as is, its working tree has local changes",
+                path
+            ),
+        )
+    }
+
+    changed
+}
+
+// Whether tracked files were changed, untracked ones like compiled Lua don't count
+fn is_dirty(repo: &Repository) -> bool {
+    let mut options = StatusOptions::new();
+
+    options.include_untracked(false).include_ignored(false);
+
+    repo.statuses(Some(&mut options))
+        .map(|statuses| !statuses.is_empty())
+        .unwrap_or(false)
+}
+
+// Fetches branches and tags from `from`, the cache or the remote itself
+fn fetch(repo: &Repository, from: &str, emitter: &mut dyn Emitter) {
+    let cb = RemoteCallbacks::new();
+    let mut fo = FetchOptions::new();
+
+    fo.remote_callbacks(cb);
+
+    let refspecs = [
+        "+refs/heads/*:refs/remotes/origin/*",
+        "+refs/tags/*:refs/tags/*",
+    ];
+
+    let result = repo
+        .remote_anonymous(from)
+        .and_then(|mut remote| remote.fetch(&refspecs, Some(&mut fo), None));
+
+    if let Err(why) = result {
+        wrong(emitter, &format!("Failed to fetch '{}' :: {}", from, why))
+    }
+
+    emitter.status("")
+}
+
+fn clone(url: &str, path: &str, emitter: &mut dyn Emitter) -> Option<Repository> {
     let cb = RemoteCallbacks::new();
     let co = CheckoutBuilder::new();
     let mut fo = FetchOptions::new();
 
     fo.remote_callbacks(cb);
 
-    match RepoBuilder::new()
+    let repo = match RepoBuilder::new()
         .fetch_options(fo)
         .with_checkout(co)
         .clone(url, Path::new(path))
     {
-        Ok(_) => (),
-        Err(why) => wrong(&format!("Failed to download '{}' :: {}", url, why)),
+        Ok(repo) => Some(repo),
+        Err(why) => {
+            wrong(emitter, &format!("Failed to download '{}' :: {}", url, why));
+            None
+        }
+    };
+
+    emitter.status("");
+
+    repo
+}
+
+// Checks out whatever `spec` names, a commit hash, a ref or `HEAD`
+fn checkout(repo: &Repository, spec: &str, emitter: &mut dyn Emitter) -> Option<String> {
+    let result = repo
+        .revparse_single(spec)
+        .and_then(|object| object.peel_to_commit())
+        .and_then(|found| {
+            repo.checkout_tree(found.as_object(), Some(CheckoutBuilder::new().force()))?;
+            repo.set_head_detached(found.id())?;
+
+            Ok(found.id().to_string())
+        });
+
+    match result {
+        Ok(commit) => Some(commit),
+        Err(why) => {
+            wrong(
+                emitter,
+                &format!("Failed to check out '{}' :: {}", spec, why),
+            );
+            None
+        }
     }
+}
 
-    println!()
+fn wrong(emitter: &mut dyn Emitter, message: &str) {
+    emitter.emit(&response!(Response::Wrong(message)))
 }
 
-fn wrong(message: &str) {
-    println!("{} {}", "wrong:".red().bold(), message)
+fn weird(emitter: &mut dyn Emitter, message: &str) {
+    emitter.emit(&response!(Response::Weird(message)))
 }
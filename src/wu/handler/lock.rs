@@ -0,0 +1,98 @@
+use toml::Value;
+
+use super::dependency::Reference;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const LOCK_FILE: &str = "wu.lock";
+
+// A dependency pinned to the commit it resolved to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locked {
+    pub url: String,
+    pub reference: Reference,
+    pub commit: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lock {
+    pub packages: BTreeMap<String, Locked>,
+}
+
+impl Lock {
+    // A missing or unreadable lockfile locks nothing
+    pub fn read(path: &str) -> Self {
+        let mut lock = Lock::default();
+
+        if !Path::new(path).exists() {
+            return lock;
+        }
+
+        let content = fs::read_to_string(path).unwrap_or_default();
+
+        if let Ok(Value::Table(table)) = toml::from_str::<Value>(&content) {
+            for (name, package) in table {
+                let field = |key: &str| package.get(key).and_then(Value::as_str).map(String::from);
+
+                let reference = match Reference::from_table(&package) {
+                    Ok(reference) => reference,
+                    Err(_) => continue,
+                };
+
+                if let (Some(url), Some(commit)) = (field("url"), field("commit")) {
+                    lock.packages.insert(
+                        name,
+                        Locked {
+                            url,
+                            reference,
+                            commit,
+                        },
+                    );
+                }
+            }
+        }
+
+        lock
+    }
+
+    pub fn write(&self, path: &str) -> io::Result<()> {
+        let mut content = String::from("# Generated by `wu sync`, move it forward with `wu update`\n");
+
+        for (name, package) in self.packages.iter() {
+            content.push_str(&format!(
+                "\n[{}]\nurl = {}\n",
+                toml_key(name),
+                Value::String(package.url.clone())
+            ));
+
+            if let Some((key, value)) = package.reference.key_value() {
+                content.push_str(&format!("{} = {}\n", key, Value::String(value.to_string())))
+            }
+
+            content.push_str(&format!("commit = {}\n", Value::String(package.commit.clone())))
+        }
+
+        fs::write(path, content)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Locked> {
+        self.packages.get(name)
+    }
+}
+
+// Bare when TOML allows it, quoted otherwise
+pub fn toml_key(name: &str) -> String {
+    let bare = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if bare {
+        name.to_string()
+    } else {
+        Value::String(name.to_string()).to_string()
+    }
+}
@@ -0,0 +1,725 @@
+use toml::Value;
+
+use std::path::Path;
+
+use super::dependency::Dependency;
+use super::lock::toml_key;
+
+use super::super::error::Response::{Note, Weird, Wrong};
+use super::super::error::{Diagnostic, Emitter};
+use super::super::lexer::Pos;
+use super::super::source::FilePath;
+
+pub const DEPENDENCIES: &str = "dependencies";
+
+// What a project says about itself under `[package]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    // the `.wu` file the package starts from, relative to where `wu` runs
+    pub entry: Option<String>,
+    // the oldest compiler the package works with
+    pub wu_version: Option<String>,
+}
+
+// Packages living under one root, sharing its lockfile and libpath
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    // directories with a `wu.toml` each, relative to the root
+    pub members: Vec<String>,
+}
+
+// A checked `wu.toml`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub package: Option<Package>,
+    pub workspace: Option<Workspace>,
+    pub libpath: String,
+    pub out_dir: Option<String>,
+    // as written, path dependencies being relative to the manifest
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Manifest {
+    // The manifest unless something in it is wrong, along with everything that was wrong or weird
+    pub fn parse(path: &str, contents: &str) -> (Option<Self>, Vec<Diagnostic>) {
+        let mut checker = Checker {
+            path,
+            lines: split(contents),
+            diagnostics: Vec::new(),
+        };
+
+        let table = match toml::from_str::<Value>(contents) {
+            Ok(Value::Table(table)) => table,
+            Ok(_) => Default::default(),
+
+            Err(why) => {
+                checker.syntax(why);
+
+                return (None, checker.diagnostics);
+            }
+        };
+
+        let mut manifest = Manifest {
+            package: None,
+            workspace: None,
+            libpath: "libs/".to_string(),
+            out_dir: None,
+            dependencies: Vec::new(),
+        };
+
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                "package" => manifest.package = checker.package(value),
+
+                "workspace" => manifest.workspace = checker.workspace(value),
+
+                DEPENDENCIES => manifest.dependencies = checker.dependencies(value),
+
+                "libpath" => {
+                    if let Some(libpath) = checker.string(value, "", key) {
+                        manifest.libpath = libpath
+                    }
+                }
+
+                "build" => manifest.out_dir = checker.build(value),
+
+                _ => checker.unknown(
+                    "",
+                    key,
+                    "expected `package`, `workspace`, `dependencies`, `libpath` or `build`",
+                ),
+            }
+        }
+
+        // keys come out of the table sorted, diagnostics are read top to bottom
+        checker
+            .diagnostics
+            .sort_by_key(|diagnostic| diagnostic.span.as_ref().map(|pos| (pos.0).0));
+
+        let wrong = checker.diagnostics.iter().any(Diagnostic::is_wrong);
+
+        (
+            if wrong { None } else { Some(manifest) },
+            checker.diagnostics,
+        )
+    }
+
+    // Prints whatever the manifest at `path` gets wrong, `None` if it can't be used
+    pub fn read(path: &str, emitter: &mut dyn Emitter) -> Option<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+
+            Err(why) => {
+                emitter.emit(&response!(Wrong(format!(
+                    "failed to read `{}`: {}",
+                    path, why
+                ))));
+
+                return None;
+            }
+        };
+
+        let (manifest, diagnostics) = Manifest::parse(path, &contents);
+
+        for diagnostic in diagnostics.iter() {
+            emitter.emit(diagnostic)
+        }
+
+        manifest
+    }
+}
+
+// Validates one manifest, pointing diagnostics at the keys they are about
+struct Checker<'a> {
+    path: &'a str,
+    lines: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn package(&mut self, value: &Value) -> Option<Package> {
+        let table = match *value {
+            Value::Table(ref table) => table,
+            _ => {
+                self.wrong(
+                    "",
+                    "package",
+                    "expected `[package]` to be a table".to_string(),
+                );
+                return None;
+            }
+        };
+
+        let mut package = Package {
+            name: String::new(),
+            version: None,
+            authors: Vec::new(),
+            entry: None,
+            wu_version: None,
+        };
+
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                "name" => {
+                    if let Some(name) = self.string(value, "package", key) {
+                        let valid = !name.is_empty()
+                            && name
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+                        if !valid {
+                            self.wrong("package", key, format!("invalid package name `{}`", name));
+                            self.note("expected letters, digits, `_` and `-`")
+                        }
+
+                        package.name = name
+                    }
+                }
+
+                "version" => {
+                    if let Some(version) = self.string(value, "package", key) {
+                        if parse_version(&version).map(|parts| parts.len()) != Some(3) {
+                            self.wrong("package", key, format!("invalid version `{}`", version));
+                            self.note("expected a version like `0.1.0`")
+                        }
+
+                        package.version = Some(version)
+                    }
+                }
+
+                "authors" => {
+                    let authors = match *value {
+                        Value::Array(ref authors) => authors
+                            .iter()
+                            .map(|author| author.as_str().map(String::from))
+                            .collect::<Option<Vec<_>>>(),
+
+                        _ => None,
+                    };
+
+                    match authors {
+                        Some(authors) => package.authors = authors,
+                        None => self.wrong(
+                            "package",
+                            key,
+                            "expected `authors` to be an array of strings".to_string(),
+                        ),
+                    }
+                }
+
+                "entry" => {
+                    if let Some(entry) = self.string(value, "package", key) {
+                        package.entry = self.entry(&entry)
+                    }
+                }
+
+                "wu-version" => {
+                    if let Some(required) = self.string(value, "package", key) {
+                        self.wu_version(&required, key);
+
+                        package.wu_version = Some(required)
+                    }
+                }
+
+                _ => self.unknown(
+                    "package",
+                    key,
+                    "expected `name`, `version`, `authors`, `entry` or `wu-version`",
+                ),
+            }
+        }
+
+        if !table.contains_key("name") {
+            self.wrong("", "package", "missing `name` in `[package]`".to_string());
+
+            return None;
+        }
+
+        Some(package)
+    }
+
+    fn workspace(&mut self, value: &Value) -> Option<Workspace> {
+        let table = match *value {
+            Value::Table(ref table) => table,
+            _ => {
+                self.wrong(
+                    "",
+                    "workspace",
+                    "expected `[workspace]` to be a table".to_string(),
+                );
+                return None;
+            }
+        };
+
+        let mut workspace = Workspace {
+            members: Vec::new(),
+        };
+
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                "members" => {
+                    let members = match *value {
+                        Value::Array(ref members) => members
+                            .iter()
+                            .map(|member| member.as_str().map(String::from))
+                            .collect::<Option<Vec<_>>>(),
+
+                        _ => None,
+                    };
+
+                    let members = match members {
+                        Some(members) => members,
+                        None => {
+                            self.wrong(
+                                "workspace",
+                                key,
+                                "expected `members` to be an array of paths".to_string(),
+                            );
+
+                            continue;
+                        }
+                    };
+
+                    let base = Path::new(self.path)
+                        .parent()
+                        .unwrap_or_else(|| Path::new(""));
+
+                    for member in members.iter() {
+                        if !base.join(member).join("wu.toml").is_file() {
+                            self.wrong(
+                                "workspace",
+                                key,
+                                format!("no `wu.toml` in workspace member `{}`", member),
+                            )
+                        }
+                    }
+
+                    workspace.members = members
+                }
+
+                _ => self.unknown("workspace", key, "expected `members`"),
+            }
+        }
+
+        Some(workspace)
+    }
+
+    // `entry = "src/main"` names a module, the way `import` would
+    fn entry(&mut self, entry: &str) -> Option<String> {
+        let base = Path::new(self.path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let module = base.join(entry.trim_end_matches(".wu"));
+
+        let candidates = [module.with_extension("wu"), module.join("init.wu")];
+
+        match candidates.iter().find(|candidate| candidate.is_file()) {
+            Some(file) => Some(file.display().to_string()),
+
+            None => {
+                self.wrong(
+                    "package",
+                    "entry",
+                    format!("no module `{}` to enter", entry),
+                );
+                self.note(&format!(
+                    "expected `{0}.wu` or `{0}/init.wu` next to `{1}`",
+                    entry.trim_end_matches(".wu"),
+                    self.path
+                ));
+
+                None
+            }
+        }
+    }
+
+    fn wu_version(&mut self, required: &str, key: &str) {
+        let current = env!("CARGO_PKG_VERSION");
+
+        match (parse_version(required), parse_version(current)) {
+            (Some(required_parts), Some(current_parts)) => {
+                if padded(required_parts) > padded(current_parts) {
+                    self.wrong(
+                        "package",
+                        key,
+                        format!("requires wu {} or newer, this is wu {}", required, current),
+                    )
+                }
+            }
+
+            _ => {
+                self.wrong("package", key, format!("invalid version `{}`", required));
+                self.note("expected a version like `0.1` or `0.1.0`")
+            }
+        }
+    }
+
+    fn dependencies(&mut self, value: &Value) -> Vec<Dependency> {
+        let table = match *value {
+            Value::Table(ref table) => table,
+            _ => {
+                self.wrong(
+                    "",
+                    DEPENDENCIES,
+                    "expected `[dependencies]` to be a table".to_string(),
+                );
+                self.note(r#"expected keys like `a = "user/repo"`"#);
+
+                return Vec::new();
+            }
+        };
+
+        let mut dependencies = Vec::new();
+
+        for (name, value) in table.iter() {
+            match Dependency::from_toml(name, value) {
+                Ok(dependency) => dependencies.push(dependency),
+                Err(why) => self.wrong(DEPENDENCIES, name, why),
+            }
+        }
+
+        dependencies
+    }
+
+    fn build(&mut self, value: &Value) -> Option<String> {
+        let table = match *value {
+            Value::Table(ref table) => table,
+            _ => {
+                self.wrong("", "build", "expected `[build]` to be a table".to_string());
+                return None;
+            }
+        };
+
+        let mut out_dir = None;
+
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                // `out-dir` for those who spell keys like Cargo does
+                "out_dir" | "out-dir" => out_dir = self.string(value, "build", key),
+                _ => self.unknown("build", key, "expected `out_dir`"),
+            }
+        }
+
+        out_dir
+    }
+
+    fn string(&mut self, value: &Value, table: &str, key: &str) -> Option<String> {
+        match *value {
+            Value::String(ref value) => Some(value.clone()),
+
+            _ => {
+                self.wrong(
+                    table,
+                    key,
+                    format!(
+                        "expected `{}` to be a string, found {}",
+                        key,
+                        value.type_str()
+                    ),
+                );
+
+                None
+            }
+        }
+    }
+
+    fn syntax(&mut self, why: toml::de::Error) {
+        let message = why.to_string();
+
+        // the position is shown underneath instead
+        let message = match message.rfind(" at line ") {
+            Some(i) => message[..i].to_string(),
+            None => message,
+        };
+
+        let pos = match why.line_col() {
+            Some((line, column)) => Pos(
+                (line + 1, self.lines.get(line).cloned().unwrap_or_default()),
+                (column + 1, column + 1),
+            ),
+
+            None => self.pos("", ""),
+        };
+
+        self.diagnostics.push(response!(
+            Wrong(message),
+            FilePath(self.path.to_string()),
+            pos
+        ))
+    }
+
+    fn wrong(&mut self, table: &str, key: &str, message: String) {
+        let pos = self.pos(table, key);
+
+        self.diagnostics.push(response!(
+            Wrong(message),
+            FilePath(self.path.to_string()),
+            pos
+        ))
+    }
+
+    fn unknown(&mut self, table: &str, key: &str, expected: &str) {
+        let pos = self.pos(table, key);
+
+        self.diagnostics.push(response!(
+            Weird(format!("unknown key `{}`, it is ignored", key)),
+            FilePath(self.path.to_string()),
+            pos,
+            Note(expected)
+        ))
+    }
+
+    // Adds a note to the latest diagnostic
+    fn note(&mut self, note: &str) {
+        if let Some(diagnostic) = self.diagnostics.last_mut() {
+            diagnostic.notes.push(note.to_string())
+        }
+    }
+
+    // The key itself, or failing that the header of the table it's in
+    fn pos(&self, table: &str, key: &str) -> Pos {
+        let whole = |i: usize| {
+            let line = &self.lines[i];
+            let start = line.len() - line.trim_start().len();
+
+            Pos((i + 1, line.clone()), (start + 1, line.trim_end().len()))
+        };
+
+        if let Some(i) = find_key(&self.lines, table, key) {
+            let line = &self.lines[i];
+
+            let start = line.len() - line.trim_start().len();
+            let width = line[start..]
+                .split('=')
+                .next()
+                .unwrap_or("")
+                .trim_end()
+                .len();
+
+            return Pos((i + 1, line.clone()), (start + 1, start + width));
+        }
+
+        let nested = if table.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", table, key)
+        };
+
+        if let Some((header, _)) = find_table(&self.lines, &nested) {
+            return whole(header);
+        }
+
+        if let Some((header, _)) = find_table(&self.lines, table) {
+            return whole(header);
+        }
+
+        match self.lines.first() {
+            Some(_) => whole(0),
+            None => Pos((1, String::new()), (1, 1)),
+        }
+    }
+}
+
+// `0.1.0` as `[0, 1, 0]`, ignoring pre-release and build suffixes
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let core = version.split(['-', '+']).next()?;
+
+    let parts = core
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    if parts.is_empty() || parts.len() > 3 {
+        None
+    } else {
+        Some(parts)
+    }
+}
+
+// `0.1` and `0.1.0` being the same version
+fn padded(mut parts: Vec<u64>) -> Vec<u64> {
+    parts.resize(3, 0);
+    parts
+}
+
+// Line based edits of wu.toml, leaving everything they don't touch as it was written
+
+// Sets `name = value` under `[dependencies]`, in place if it's already there
+pub fn add_dependency(contents: &str, name: &str, value: &str) -> String {
+    let mut lines = split(contents);
+    let entry = format!("{} = {}", toml_key(name), value);
+
+    if let Some(i) = find_key(&lines, DEPENDENCIES, name) {
+        lines[i] = entry;
+
+        return join(contents, lines);
+    }
+
+    // a `[dependencies.name]` table would clash with the new key
+    lines = remove_table(lines, &format!("{}.{}", DEPENDENCIES, name));
+
+    match find_table(&lines, DEPENDENCIES) {
+        Some((header, end)) => {
+            // right after the last key, before any blank lines or comments leading into the next table
+            let last = (header + 1..end)
+                .rev()
+                .find(|&i| line_key(&lines[i]).is_some())
+                .unwrap_or(header);
+
+            lines.insert(last + 1, entry)
+        }
+
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new())
+            }
+
+            lines.push(format!("[{}]", DEPENDENCIES));
+            lines.push(entry)
+        }
+    }
+
+    join(contents, lines)
+}
+
+// Drops `name` from `[dependencies]`, `None` if it wasn't there to begin with
+pub fn remove_dependency(contents: &str, name: &str) -> Option<String> {
+    let lines = split(contents);
+
+    if let Some(i) = find_key(&lines, DEPENDENCIES, name) {
+        let mut lines = lines;
+
+        lines.remove(i);
+
+        return Some(join(contents, lines));
+    }
+
+    let table = format!("{}.{}", DEPENDENCIES, name);
+
+    find_table(&lines, &table)?;
+
+    Some(join(contents, remove_table(lines, &table)))
+}
+
+fn split(contents: &str) -> Vec<String> {
+    contents.lines().map(String::from).collect()
+}
+
+// Keeps the line endings of the original
+fn join(contents: &str, lines: Vec<String>) -> String {
+    let newline = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut result = lines.join(newline);
+
+    result.push_str(newline);
+    result
+}
+
+// The name of the table a line opens, if it is a header
+fn table_name(line: &str) -> Option<String> {
+    let line = line.trim();
+
+    if !line.starts_with('[') {
+        return None;
+    }
+
+    let end = line.find(']')?;
+
+    let name = line[1..end]
+        .trim_start_matches('[')
+        .split('.')
+        .map(|part| unquote(part.trim()))
+        .collect::<Vec<_>>()
+        .join(".");
+
+    Some(name)
+}
+
+// The key a `key = value` line sets
+fn line_key(line: &str) -> Option<String> {
+    let line = line.trim();
+
+    if line.starts_with('#') || line.starts_with('[') {
+        return None;
+    }
+
+    let (key, _) = line.split_once('=')?;
+
+    Some(unquote(key.trim()))
+}
+
+fn unquote(key: &str) -> String {
+    let quoted = key.len() >= 2
+        && ((key.starts_with('"') && key.ends_with('"'))
+            || (key.starts_with('\'') && key.ends_with('\'')));
+
+    if quoted {
+        key[1..key.len() - 1].to_string()
+    } else {
+        key.to_string()
+    }
+}
+
+// Where a table's header is, and where the next one starts
+fn find_table(lines: &[String], name: &str) -> Option<(usize, usize)> {
+    let header = lines
+        .iter()
+        .position(|line| table_name(line).as_deref() == Some(name))?;
+
+    let end = lines[header + 1..]
+        .iter()
+        .position(|line| table_name(line).is_some())
+        .map(|i| header + 1 + i)
+        .unwrap_or(lines.len());
+
+    Some((header, end))
+}
+
+// The line setting `key` in `table`, the empty table being the top-level keys
+fn find_key(lines: &[String], table: &str, key: &str) -> Option<usize> {
+    let (start, end) = if table.is_empty() {
+        let end = lines
+            .iter()
+            .position(|line| table_name(line).is_some())
+            .unwrap_or(lines.len());
+
+        (0, end)
+    } else {
+        let (header, end) = find_table(lines, table)?;
+
+        (header + 1, end)
+    };
+
+    (start..end).find(|&i| line_key(&lines[i]).as_deref() == Some(key))
+}
+
+// Takes out a table along with one side of the blank lines around it
+fn remove_table(mut lines: Vec<String>, name: &str) -> Vec<String> {
+    if let Some((header, end)) = find_table(&lines, name) {
+        let spaced = header == 0 || lines[header - 1].trim().is_empty();
+
+        let last = if spaced {
+            end
+        } else {
+            (header..end)
+                .rev()
+                .find(|&i| !lines[i].trim().is_empty())
+                .map_or(end, |i| i + 1)
+        };
+
+        lines.drain(header..last);
+
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+    }
+
+    lines
+}
@@ -1,2 +1,10 @@
+pub mod cache;
+pub mod dependency;
 pub mod handler;
+pub mod lock;
+pub mod manifest;
+pub mod template;
+
+pub use self::dependency::*;
 pub use self::handler::*;
+pub use self::lock::*;
@@ -0,0 +1,191 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Stands in for the project's name, in paths as well as contents
+pub const NAME: &str = "{{name}}";
+
+pub const BUILT_IN: [&str; 3] = ["app", "lib", "love2d"];
+
+const APP_MANIFEST: &str = "\
+[package]
+name = \"{{name}}\"
+version = \"0.1.0\"
+entry = \"init\"
+
+[dependencies]
+";
+
+const LIB_SOURCE: &str = "\
+# What {{name}} has to offer, made public by the `init.wu` next to `wu.toml`
+
+pub greet: fun(name: str) -> str {
+  \"hello, \" ++ name
+}
+";
+
+// Love looks for `lover/init.lua` right next to `main.lua`, so dependencies go there
+const LOVE2D_MANIFEST: &str = "\
+libpath = \"./\"
+
+[package]
+name = \"{{name}}\"
+version = \"0.1.0\"
+entry = \"main\"
+
+[dependencies]
+lover = \"nilq/lover\"
+";
+
+const LOVE2D_CONF: &str = "\
+love: extern module {
+  pub conf: fun(t: any) {}
+}
+
+love conf = fun(t: any) {
+  t window title = \"{{name}}\"
+  t window width = 800
+  t window height = 600
+}
+";
+
+const LOVE2D_MAIN: &str = "\
+import lover
+
+love: extern module {
+  pub load:   fun() {}
+  pub update: fun(dt: float) {}
+  pub draw:   fun() {}
+
+  pub graphics: any
+}
+
+love load = fun() {}
+
+love update = fun(dt: float) {}
+
+love draw = fun() {
+  love graphics print(\"Hello from {{name}}!\", 400, 300)
+}
+";
+
+// A project skeleton, files relative to the project and what goes in them
+pub struct Template {
+    pub files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Template {
+    // Templates in `$WU_HOME/templates` come first, so they can replace the built-in ones
+    pub fn find(name: &str) -> Option<Self> {
+        user(name).or_else(|| built_in(name))
+    }
+
+    // Writes the skeleton into `root`, leaving files that are already there alone.
+    // Hands back the files that were skipped
+    pub fn write(&self, root: &Path, name: &str) -> Result<Vec<PathBuf>, String> {
+        let mut skipped = Vec::new();
+
+        for (path, content) in self.files.iter() {
+            let path = root.join(path.display().to_string().replace(NAME, name));
+
+            if path.exists() {
+                skipped.push(path);
+
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|why| why.to_string())?
+            }
+
+            // only text has names in it
+            let content = match std::str::from_utf8(content) {
+                Ok(text) => text.replace(NAME, name).into_bytes(),
+                Err(_) => content.clone(),
+            };
+
+            fs::write(&path, content).map_err(|why| format!("{} :: {}", path.display(), why))?
+        }
+
+        Ok(skipped)
+    }
+}
+
+// `$WU_HOME/templates/`, holding a directory per template
+pub fn user_dir() -> Option<PathBuf> {
+    let home = env::var("WU_HOME").ok()?;
+
+    Some(Path::new(&home).join("templates"))
+}
+
+fn built_in(name: &str) -> Option<Template> {
+    let files: Vec<(&str, &str)> = match name {
+        "app" => vec![
+            ("wu.toml", APP_MANIFEST),
+            ("init.wu", "import src\n"),
+            ("src/init.wu", ""),
+        ],
+
+        "lib" => vec![
+            ("wu.toml", APP_MANIFEST),
+            ("init.wu", "pub import src\n"),
+            ("src/init.wu", LIB_SOURCE),
+        ],
+
+        "love2d" => vec![
+            ("wu.toml", LOVE2D_MANIFEST),
+            ("conf.wu", LOVE2D_CONF),
+            ("main.wu", LOVE2D_MAIN),
+        ],
+
+        _ => return None,
+    };
+
+    Some(Template {
+        files: files
+            .into_iter()
+            .map(|(path, content)| (PathBuf::from(path), content.as_bytes().to_vec()))
+            .collect(),
+    })
+}
+
+// Whatever is in the template's directory, with a manifest like the `app` one if it has none
+fn user(name: &str) -> Option<Template> {
+    let dir = user_dir()?.join(name);
+
+    if !dir.is_dir() {
+        return None;
+    }
+
+    let mut files = Vec::new();
+
+    collect(&dir, Path::new(""), &mut files);
+
+    if !files.iter().any(|(path, _)| path == Path::new("wu.toml")) {
+        files.push((PathBuf::from("wu.toml"), APP_MANIFEST.as_bytes().to_vec()))
+    }
+
+    Some(Template { files })
+}
+
+fn collect(dir: &Path, relative: &Path, files: &mut Vec<(PathBuf, Vec<u8>)>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+
+        if entry.file_name() == ".git" {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect(&path, &relative, files)
+        } else if let Ok(content) = fs::read(&path) {
+            files.push((relative, content))
+        }
+    }
+}
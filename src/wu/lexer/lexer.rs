@@ -48,6 +48,7 @@ impl<'l> Lexer<'l> {
                 "module",
                 "extern",
                 "struct",
+                "enum",
                 "new",
                 "implement",
                 "import",
@@ -95,7 +96,7 @@ impl<'l> Lexer<'l> {
         lexer
     }
 
-    pub fn match_token(&mut self) -> Result<Option<Token>, ()> {
+    pub fn match_token(&mut self) -> Result<Option<Token>, Diagnostic> {
         for matcher in &mut self.matchers {
             match self.tokenizer.try_match_token(matcher.as_ref())? {
                 Some(t) => return Ok(Some(t)),
@@ -108,9 +109,9 @@ impl<'l> Lexer<'l> {
 }
 
 impl<'l> Iterator for Lexer<'l> {
-    type Item = Result<Token, ()>;
+    type Item = Result<Token, Diagnostic>;
 
-    fn next(&mut self) -> Option<Result<Token, ()>> {
+    fn next(&mut self) -> Option<Result<Token, Diagnostic>> {
         let token = match self.match_token() {
             Ok(hmm) => match hmm {
                 Some(n) => n,
@@ -135,7 +136,7 @@ impl<'l> Iterator for Lexer<'l> {
                 }
             },
 
-            Err(_) => return Some(Err(())),
+            Err(diagnostic) => return Some(Err(diagnostic)),
         };
 
         match token.token_type {
@@ -38,13 +38,13 @@ macro_rules! token {
 }
 
 pub trait Matcher<'t> {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()>;
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic>;
 }
 
 pub struct CommentMatcher;
 
 impl<'t> Matcher<'t> for CommentMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         if tokenizer.peek_range(1).unwrap_or_else(String::new) == "#" {
             while !tokenizer.end() && tokenizer.peek() != Some('\n') {
                 tokenizer.advance()
@@ -72,7 +72,7 @@ impl ConstantStringMatcher {
 }
 
 impl<'t> Matcher<'t> for ConstantStringMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         for constant in self.constants {
             let len = constant.len();
             let c = match tokenizer.peek_range(len) {
@@ -113,7 +113,7 @@ impl ConstantCharMatcher {
 }
 
 impl<'t> Matcher<'t> for ConstantCharMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         let c = tokenizer.peek().unwrap();
 
         for constant in self.constants {
@@ -137,7 +137,7 @@ impl<'t> Matcher<'t> for ConstantCharMatcher {
 pub struct StringLiteralMatcher;
 
 impl<'t> Matcher<'t> for StringLiteralMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         let mut raw_marker = false;
 
         let mut pos = tokenizer.pos;
@@ -303,7 +303,7 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 pub struct IdentifierMatcher;
 
 impl<'t> Matcher<'t> for IdentifierMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         let peeked = tokenizer.peek().unwrap();
 
         if !peeked.is_alphabetic() && peeked != '_' {
@@ -323,7 +323,7 @@ impl<'t> Matcher<'t> for IdentifierMatcher {
 pub struct NumberLiteralMatcher;
 
 impl<'t> Matcher<'t> for NumberLiteralMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         let mut accum = String::new();
 
         let curr = tokenizer.next().unwrap();
@@ -340,6 +340,11 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
         while !tokenizer.end() {
             let current = tokenizer.peek().unwrap();
             if !current.is_whitespace() && current.is_digit(10) || current == '.' {
+                // `1..10` is a range, not a float
+                if current == '.' && tokenizer.peek_n(1) == Some('.') {
+                    break;
+                }
+
                 if current == '.' && accum.contains('.') {
                     let pos = tokenizer.pos;
 
@@ -403,7 +408,7 @@ impl KeyMatcher {
 }
 
 impl<'t> Matcher<'t> for KeyMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         for constant in self.constants {
             if let Some(s) = tokenizer.peek_range(constant.len()) {
                 if s == *constant {
@@ -430,7 +435,7 @@ impl<'t> Matcher<'t> for KeyMatcher {
 pub struct EOLMatcher;
 
 impl<'t> Matcher<'t> for EOLMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         if tokenizer.peek() == Some('\n') {
             tokenizer.pos.0 += 1;
             tokenizer.pos.1 = 0;
@@ -446,7 +451,7 @@ impl<'t> Matcher<'t> for EOLMatcher {
 pub struct WhitespaceMatcher;
 
 impl<'t> Matcher<'t> for WhitespaceMatcher {
-    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+    fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, Diagnostic> {
         let string = tokenizer.collect_while(|c| c.is_whitespace() && c != '\n');
 
         if !string.is_empty() {
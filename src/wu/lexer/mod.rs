@@ -3,6 +3,7 @@ pub mod matcher;
 pub mod token;
 pub mod tokenizer;
 
+use super::error::Diagnostic;
 use super::source::Source;
 
 pub use self::lexer::*;
@@ -51,7 +51,13 @@ impl fmt::Display for Pos {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let linepad = format!("{:5} │", " ").blue().bold();
         let lineno = format!("{:5} │ ", (self.0).0).blue().bold();
-        let mut mark = (self.0).1[(self.1).0.saturating_sub(1)..(self.1).1].to_string();
+        let line = &(self.0).1;
+
+        // spans past the end of the line (EOL, EOF) are clamped to it
+        let end = (self.1).1.min(line.len());
+        let start = (self.1).0.saturating_sub(1).min(end);
+
+        let mut mark = line[start..end].to_string();
 
         if mark.split_whitespace().count() == 0 {
             mark = format!("{:─>count$}", ">".red().bold(), count = mark.len());
@@ -61,7 +67,7 @@ impl fmt::Display for Pos {
 
         let mut arrows = format!("{: <count$}", " ", count = (self.1).0);
 
-        for _ in 0..(self.1).1 - (self.1).0 + 1 {
+        for _ in 0..((self.1).1 + 1).saturating_sub((self.1).0) {
             arrows.push('^')
         }
 
@@ -70,9 +76,9 @@ impl fmt::Display for Pos {
             "\n{}\n{}{}{}{}\n{}{}",
             linepad,
             lineno,
-            &(self.0).1[..(self.1).0.saturating_sub(1)],
+            &line[..start],
             mark,
-            &(self.0).1[(self.1).1..],
+            &line[end..],
             linepad,
             arrows.red().bold()
         )
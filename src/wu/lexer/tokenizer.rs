@@ -1,5 +1,5 @@
 use super::token::*;
-use super::{Matcher, Source};
+use super::{Diagnostic, Matcher, Source};
 
 pub struct Snapshot {
     pub index: usize,
@@ -89,7 +89,7 @@ impl<'t> Tokenizer<'t> {
             .pos
     }
 
-    pub fn try_match_token(&mut self, matcher: &dyn Matcher<'t>) -> Result<Option<Token>, ()> {
+    pub fn try_match_token(&mut self, matcher: &dyn Matcher<'t>) -> Result<Option<Token>, Diagnostic> {
         if self.end() {
             return Ok(Some(Token::new(
                 TokenType::EOF,
@@ -1,8 +1,11 @@
 #[macro_use]
 pub mod error;
+pub mod bundle;
 pub mod compiler;
+pub mod driver;
 pub mod handler;
 pub mod lexer;
 pub mod parser;
+pub mod repl;
 pub mod source;
 pub mod visitor;
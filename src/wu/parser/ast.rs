@@ -69,9 +69,11 @@ pub enum ExpressionNode {
     Module(Rc<Expression>),
     Extern(Type, Option<String>),
     ExternExpression(Rc<Expression>),
-    Struct(String, Vec<(String, Type)>, String),
+    Struct(String, Vec<(String, Type)>, String, Vec<(String, Vec<Type>)>), // type parameters and their bounds
     Trait(String, Vec<(String, Type)>),
+    Enum(String, Vec<(String, Vec<Type>)>, String),
     Initialization(Rc<Expression>, Vec<(String, Expression)>),
+    Switch(Rc<Expression>, Vec<(Pattern, Option<Expression>, Expression)>), // guard: Option<Expression>
 
     Empty,
     EOF,
@@ -89,6 +91,30 @@ impl Expression {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternNode {
+    Wildcard,
+    Binding(String),                            // unless it names a variant of what's switched on
+    Value(Expression),                          // compared to what's switched on
+    Range(Expression, Expression),              // `1..10`, from the first up to but not including the last
+    Variant(String, Vec<Pattern>),              // `Circle(r)`, taking apart the variant's payload
+    Tuple(Vec<Pattern>),                        // `(a, 0)`
+    Struct(Expression, Vec<(String, Pattern)>), // `Player { x, y: 0 }`
+    Alternative(Vec<Pattern>),                  // `1 | 2`
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    pub node: PatternNode,
+    pub pos: Pos,
+}
+
+impl Pattern {
+    pub fn new(node: PatternNode, pos: Pos) -> Self {
+        Pattern { node, pos }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
     Add,
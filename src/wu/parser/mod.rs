@@ -1,6 +1,7 @@
 pub mod ast;
 pub mod parser;
 
+use super::error::Diagnostic;
 use super::lexer::*;
 use super::source::*;
 use super::visitor::*;
@@ -1,4 +1,5 @@
-use super::super::error::Response::Wrong;
+use super::super::error::dedup;
+use super::super::error::Response::{Note, Wrong};
 use super::*;
 
 use std::rc::Rc;
@@ -9,6 +10,9 @@ pub struct Parser<'p> {
     source: &'p Source,
     in_sequence: bool,
     tmp_sequence: bool,
+    diagnostics: Vec<Diagnostic>,
+    // type parameters of the functions and structs being parsed, and their bounds
+    generics: Vec<(String, Vec<Type>)>,
 }
 
 impl<'p> Parser<'p> {
@@ -19,20 +23,56 @@ impl<'p> Parser<'p> {
             index: 0,
             in_sequence: false,
             tmp_sequence: false,
+            diagnostics: Vec::new(),
+            generics: Vec::new(),
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Statement>, ()> {
+    pub fn parse(&mut self) -> Result<Vec<Statement>, Vec<Diagnostic>> {
         let mut ast = Vec::new();
 
-        while self.remaining() > 0 {
-            ast.push(self.parse_statement()?)
+        while let Ok(Some(statement)) = self._parse_statement() {
+            ast.push(statement)
         }
 
-        Ok(ast)
+        if self.diagnostics.is_empty() {
+            Ok(ast)
+        } else {
+            let mut diagnostics = self.diagnostics.drain(..).collect();
+
+            dedup(&mut diagnostics);
+
+            Err(diagnostics)
+        }
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, ()> {
+    // Skips the rest of a broken statement, stopping after the next new line
+    // or closing brace that isn't part of a block opened inside the statement
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+
+        while self.remaining() > 0 {
+            let token = self.current();
+
+            self.index += 1;
+
+            match (token.token_type, token.lexeme.as_str()) {
+                (TokenType::EOL, _) if depth == 0 => break,
+                (TokenType::Symbol, "{") => depth += 1,
+                (TokenType::Symbol, "}") => {
+                    if depth == 0 {
+                        break;
+                    }
+
+                    depth -= 1
+                }
+
+                _ => (),
+            }
+        }
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, Diagnostic> {
         use self::TokenType::*;
 
         while self.current_type() == EOL && self.remaining() != 0 {
@@ -47,6 +87,18 @@ impl<'p> Parser<'p> {
                 let position = self.current_position();
                 let name = self.eat_type(&Identifier)?;
 
+                // `Stack<T>: struct`, as long as it doesn't turn out to be a comparison
+                let mut generics = Vec::new();
+
+                if self.current_lexeme() == "<" {
+                    let backup = self.index;
+
+                    match self.parse_type_params() {
+                        Ok(params) if self.current_lexeme() == ":" => generics = params,
+                        _ => self.index = backup,
+                    }
+                }
+
                 let mut splat_names = vec![name.clone()];
 
                 while self.current_lexeme() == "," {
@@ -61,6 +113,51 @@ impl<'p> Parser<'p> {
                         let position = self.current_position();
                         let backup = self.index;
 
+                        if !generics.is_empty() {
+                            let scope = self.generics.len();
+
+                            self.generics.extend(generics.iter().cloned());
+
+                            let right = self.parse_right_hand(name.clone());
+
+                            self.generics.truncate(scope);
+
+                            return match right? {
+                                Some(mut right) => {
+                                    if let ExpressionNode::Struct(.., ref mut params) = right.node {
+                                        *params = generics
+                                    } else {
+                                        return Err(response!(
+                                            Wrong("only structs take type parameters like this"),
+                                            self.source.file,
+                                            right.pos,
+                                            Note("functions take them as `fun<T>(...)`")
+                                        ));
+                                    }
+
+                                    let statement = Statement::new(
+                                        StatementNode::Variable(
+                                            Type::from(TypeNode::Nil),
+                                            name,
+                                            Some(right),
+                                            false
+                                        ),
+                                        self.span_from(position),
+                                    );
+
+                                    self.new_line()?;
+
+                                    Ok(statement)
+                                }
+
+                                None => Err(response!(
+                                    Wrong("expected struct after type parameters"),
+                                    self.source.file,
+                                    position
+                                )),
+                            };
+                        }
+
                         if splat_names.len() == 1 {
                             if let Some(right) = self.parse_right_hand(name.clone())? {
                                 let statement = Statement::new(
@@ -293,6 +390,15 @@ impl<'p> Parser<'p> {
 
                     let name = name_parser.parse_expression()?;
 
+                    // `implement Stack<T>`, bringing the struct's type parameters into scope
+                    let scope = self.generics.len();
+
+                    if self.current_lexeme() == "<" {
+                        let generics = self.parse_type_params()?;
+
+                        self.generics.extend(generics)
+                    }
+
                     self.next_newline()?;
 
                     let mut parent = None;
@@ -306,9 +412,11 @@ impl<'p> Parser<'p> {
 
                     self.expect_lexeme("{")?;
 
-                    let body = self.parse_expression()?;
+                    let body = self.parse_expression();
 
-                    Statement::new(StatementNode::Implement(name, body, parent), pos)
+                    self.generics.truncate(scope);
+
+                    Statement::new(StatementNode::Implement(name, body?, parent), pos)
                 }
 
                 _ => {
@@ -349,7 +457,7 @@ impl<'p> Parser<'p> {
         Ok(statement)
     }
 
-    fn maybe_splat(&mut self, expr: Expression) -> Result<Expression, ()> {
+    fn maybe_splat(&mut self, expr: Expression) -> Result<Expression, Diagnostic> {
         match self.current_lexeme().as_str() {
             "," => {
                 let position = expr.pos.clone();
@@ -376,7 +484,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn try_parse_compound(&mut self, left: &Expression) -> Result<Option<Statement>, ()> {
+    fn try_parse_compound(&mut self, left: &Expression) -> Result<Option<Statement>, Diagnostic> {
         if self.current_type() != TokenType::Operator {
             return Ok(None);
         }
@@ -416,7 +524,7 @@ impl<'p> Parser<'p> {
         Ok(result)
     }
 
-    fn parse_right_hand(&mut self, name: String) -> Result<Option<Expression>, ()> {
+    fn parse_right_hand(&mut self, name: String) -> Result<Option<Expression>, Diagnostic> {
         let declaration = match self.current_lexeme().as_str() {
             "extern" => {
                 let position = self.current_position();
@@ -481,6 +589,29 @@ impl<'p> Parser<'p> {
                         name,
                         params,
                         format!("{}{}", self.source.file, position),
+                        Vec::new(),
+                    ),
+                    position,
+                ))
+            },
+
+            "enum" => {
+                let mut position = self.current_position();
+
+                self.next()?;
+                self.next_newline()?;
+
+                position = self.span_from(position);
+
+                self.expect_lexeme("{")?;
+
+                let variants = self.parse_block_of(("{", "}"), &Self::_parse_variant_comma)?;
+
+                Some(Expression::new(
+                    ExpressionNode::Enum(
+                        name,
+                        variants,
+                        format!("{}{}", self.source.file, position),
                     ),
                     position,
                 ))
@@ -518,10 +649,27 @@ impl<'p> Parser<'p> {
         Ok(declaration)
     }
 
-    fn parse_function(&mut self) -> Result<Expression, ()> {
-        let mut position = self.current_position();
+    fn parse_function(&mut self) -> Result<Expression, Diagnostic> {
+        let position = self.current_position();
 
         self.next()?;
+
+        let scope = self.generics.len();
+
+        if self.current_lexeme() == "<" {
+            let generics = self.parse_type_params()?;
+
+            self.generics.extend(generics)
+        }
+
+        let function = self.parse_function_rest(position);
+
+        self.generics.truncate(scope);
+
+        function
+    }
+
+    fn parse_function_rest(&mut self, mut position: Pos) -> Result<Expression, Diagnostic> {
         self.next_newline()?;
 
         let mut params = if self.current_lexeme() == "(" {
@@ -560,7 +708,7 @@ impl<'p> Parser<'p> {
         ))
     }
 
-    fn parse_expression(&mut self) -> Result<Expression, ()> {
+    fn parse_expression(&mut self) -> Result<Expression, Diagnostic> {
         let atom = self.parse_atom()?;
 
         if self.current_type() == TokenType::Operator {
@@ -570,7 +718,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn parse_atom(&mut self) -> Result<Expression, ()> {
+    fn parse_atom(&mut self) -> Result<Expression, Diagnostic> {
         use self::TokenType::*;
 
         if self.remaining() == 0 {
@@ -730,25 +878,8 @@ impl<'p> Parser<'p> {
                         "switch" => {
                             self.next()?;
 
-                            let right_hand = self.parse_expression()?;
-                            let position = right_hand.pos.clone();
-
-                            let mut block_scope = Vec::new(); // switch is actually a block with a declaration + if statement :)) #funfacts
-
-                            let name = format!("__switch_tmp_{}", self.remaining());
-
-                            let decl = Statement::new(
-                                StatementNode::Variable(
-                                    Type::from(TypeNode::Nil),
-                                    name.clone(),
-                                    Some(right_hand),
-                                    false
-                                ),
-                                position.clone(),
-                            );
-
-                            let right_template =
-                                Expression::new(ExpressionNode::Identifier(name), position.clone());
+                            let subject = self.parse_expression()?;
+                            let position = self.span_from(position);
 
                             self.next_newline()?;
 
@@ -756,31 +887,41 @@ impl<'p> Parser<'p> {
 
                             self.next_newline()?;
 
-                            let mut branches = Vec::new();
+                            let mut arms = Vec::new();
 
                             loop {
-                                let mut branch_position = self.current_position();
+                                let pattern = self.parse_pattern()?;
 
-                                let left = self.parse_expression()?;
+                                let guard = if self.current_lexeme() == "if" {
+                                    self.next()?;
 
-                                let condition = Expression::new(
-                                    ExpressionNode::Binary(
-                                        Rc::new(left),
-                                        super::Operator::Eq,
-                                        Rc::new(right_template.clone()),
-                                    ),
-                                    branch_position.clone(),
-                                );
+                                    Some(self.parse_expression()?)
+                                } else {
+                                    None
+                                };
 
                                 self.eat_lexeme("=>")?;
 
                                 self.next_newline()?;
 
-                                branch_position = self.span_from(branch_position);
-
                                 let body = self.parse_expression()?;
 
-                                branches.push((Some(condition), body, branch_position));
+                                // arms are blocks, whatever they were written as
+                                let body = if let ExpressionNode::Block(_) = body.node {
+                                    body
+                                } else {
+                                    let position = body.pos.clone();
+
+                                    Expression::new(
+                                        ExpressionNode::Block(vec![Statement::new(
+                                            StatementNode::Expression(body),
+                                            position.clone(),
+                                        )]),
+                                        position,
+                                    )
+                                };
+
+                                arms.push((pattern, guard, body));
 
                                 self.next_newline()?;
 
@@ -791,24 +932,10 @@ impl<'p> Parser<'p> {
 
                             self.eat_lexeme("}")?;
 
-                            let primary = branches.remove(0);
-
-                            let if_pattern = Expression::new(
-                                ExpressionNode::If(
-                                    Rc::new(primary.0.unwrap()),
-                                    Rc::new(primary.1),
-                                    Some(branches),
-                                ),
-                                primary.2.clone(),
-                            );
-
-                            let if_pattern_stmt =
-                                Statement::new(StatementNode::Expression(if_pattern), primary.2);
-
-                            block_scope.push(decl);
-                            block_scope.push(if_pattern_stmt);
-
-                            Expression::new(ExpressionNode::Block(block_scope), position)
+                            Expression::new(
+                                ExpressionNode::Switch(Rc::new(subject), arms),
+                                position,
+                            )
                         }
 
                         "for" => {
@@ -975,7 +1102,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn parse_postfix(&mut self, expression: Expression) -> Result<Expression, ()> {
+    fn parse_postfix(&mut self, expression: Expression) -> Result<Expression, Diagnostic> {
         if self.remaining() == 0 {
             return Ok(expression);
         }
@@ -1096,7 +1223,176 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn parse_binary(&mut self, left: Expression, min_prec: usize) -> Result<Expression, ()> {
+    fn parse_pattern(&mut self) -> Result<Pattern, Diagnostic> {
+        let position = self.current_position();
+
+        let mut alternatives = vec![self.parse_single_pattern()?];
+
+        while self.current_lexeme() == "|" && self.current_type() == TokenType::Symbol {
+            self.next()?;
+            self.next_newline()?;
+
+            alternatives.push(self.parse_single_pattern()?)
+        }
+
+        if alternatives.len() > 1 {
+            Ok(Pattern::new(
+                PatternNode::Alternative(alternatives),
+                self.span_from(position),
+            ))
+        } else {
+            Ok(alternatives.remove(0))
+        }
+    }
+
+    fn parse_single_pattern(&mut self) -> Result<Pattern, Diagnostic> {
+        use self::TokenType::*;
+
+        let position = self.current_position();
+
+        let node = match (self.current_type(), self.current_lexeme().as_str()) {
+            (Identifier, _) => {
+                let mut path = Expression::new(
+                    ExpressionNode::Identifier(self.current_lexeme()),
+                    position.clone(),
+                );
+
+                let mut name = self.eat()?;
+
+                // `Shape Circle`, a path to what's inside a module or enum
+                while self.remaining() > 0 && self.current_type() == Identifier {
+                    let index_position = self.current_position();
+
+                    name = self.eat()?;
+
+                    path = Expression::new(
+                        ExpressionNode::Index(
+                            Rc::new(path),
+                            Rc::new(Expression::new(
+                                ExpressionNode::Identifier(name.clone()),
+                                index_position,
+                            )),
+                            false,
+                        ),
+                        self.span_from(position.clone()),
+                    )
+                }
+
+                match self.current_lexeme().as_str() {
+                    "(" => PatternNode::Variant(
+                        name,
+                        self.parse_block_of(("(", ")"), &Self::_parse_pattern_comma)?,
+                    ),
+
+                    "{" => PatternNode::Struct(
+                        path,
+                        self.parse_block_of(("{", "}"), &Self::_parse_field_pattern_comma)?,
+                    ),
+
+                    _ => match path.node {
+                        ExpressionNode::Identifier(_) if name == "_" => PatternNode::Wildcard,
+                        ExpressionNode::Identifier(_) => PatternNode::Binding(name),
+
+                        _ => PatternNode::Value(path),
+                    },
+                }
+            }
+
+            (Symbol, "(") => {
+                let mut content = self.parse_block_of(("(", ")"), &Self::_parse_pattern_comma)?;
+
+                match content.len() {
+                    0 => PatternNode::Value(Expression::new(
+                        ExpressionNode::Empty,
+                        self.span_from(position.clone()),
+                    )),
+
+                    1 => return Ok(content.remove(0)),
+
+                    _ => PatternNode::Tuple(content),
+                }
+            }
+
+            _ => {
+                let value = self.parse_atom()?;
+
+                if self.current_lexeme() == ".." {
+                    self.next()?;
+
+                    PatternNode::Range(value, self.parse_atom()?)
+                } else {
+                    PatternNode::Value(value)
+                }
+            }
+        };
+
+        Ok(Pattern::new(node, self.span_from(position)))
+    }
+
+    // `<A, B: Moving + Drawable>`, the names a function or struct is generic over
+    // and the traits whatever they stand for has to implement
+    fn parse_type_params(&mut self) -> Result<Vec<(String, Vec<Type>)>, Diagnostic> {
+        self.eat_lexeme("<")?;
+
+        let mut params = vec![self.parse_type_param()?];
+
+        while self.current_lexeme() == "," {
+            self.next()?;
+
+            params.push(self.parse_type_param()?)
+        }
+
+        self.eat_lexeme(">")?;
+
+        Ok(params)
+    }
+
+    fn parse_type_param(&mut self) -> Result<(String, Vec<Type>), Diagnostic> {
+        let name = self.eat_type(&TokenType::Identifier)?;
+
+        let mut bounds = Vec::new();
+
+        if self.current_lexeme() == ":" {
+            self.next()?;
+
+            loop {
+                self.enter_sequence();
+
+                let bound = self.parse_atom();
+
+                self.exit_sequence();
+
+                bounds.push(Type::id(Rc::new(bound?)));
+
+                if self.current_lexeme() == "+" {
+                    self.next()?
+                } else {
+                    break;
+                }
+            }
+        }
+
+        Ok((name, bounds))
+    }
+
+    // `<int, [str]>`, what a generic struct is given
+    fn parse_type_args(&mut self) -> Result<Vec<Type>, Diagnostic> {
+        self.eat_lexeme("<")?;
+
+        let mut args = vec![self.parse_type()?];
+
+        while self.current_lexeme() == "," {
+            self.next()?;
+
+            args.push(self.parse_type()?)
+        }
+
+        self.eat_lexeme(">")?;
+
+        Ok(args)
+    }
+
+    fn parse_binary(&mut self, left: Expression, min_prec: usize) -> Result<Expression, Diagnostic> {
         let mut left = left;
         let left_position = left.pos.clone();
 
@@ -1127,7 +1423,7 @@ impl<'p> Parser<'p> {
         Ok(left)
     }
 
-    fn parse_type(&mut self) -> Result<Type, ()> {
+    fn parse_type(&mut self) -> Result<Type, Diagnostic> {
         use self::TokenType::*;
 
         let mut t = match self.current_type() {
@@ -1140,14 +1436,34 @@ impl<'p> Parser<'p> {
                 "bool" => Type::from(TypeNode::Bool),
                 "self" => Type::from(TypeNode::This),
 
+                name if self.generics.iter().any(|generic| generic.0 == name) => {
+                    let bounds = self
+                        .generics
+                        .iter()
+                        .rev()
+                        .find(|generic| generic.0 == name)
+                        .map(|generic| generic.1.clone())
+                        .unwrap_or_default();
+
+                    Type::from(TypeNode::Generic(name.to_string(), bounds))
+                }
+
                 _ => {
                     self.index -= 1; // lol
                     self.enter_sequence();
-                    
-                    let a = Type::id(Rc::new(self.parse_expression()?));
-                    
+
+                    let atom = self.parse_atom()?;
+
+                    let a = if self.current_lexeme() == "<" {
+                        let args = self.parse_type_args()?;
+
+                        Type::from(TypeNode::Applied(Rc::new(atom), args))
+                    } else {
+                        Type::id(Rc::new(atom))
+                    };
+
                     self.exit_sequence();
-                    
+
                     a
                 }
             },
@@ -1320,7 +1636,7 @@ impl<'p> Parser<'p> {
         Ok(t)
     }
 
-    fn new_line(&mut self) -> Result<(), ()> {
+    fn new_line(&mut self) -> Result<(), Diagnostic> {
         if self.remaining() > 0 {
             match self.current_lexeme().as_str() {
                 "\n" => self.next(),
@@ -1339,7 +1655,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn next_newline(&mut self) -> Result<(), ()> {
+    fn next_newline(&mut self) -> Result<(), Diagnostic> {
         while self.current_lexeme() == "\n" && self.remaining() > 0 {
             self.next()?
         }
@@ -1347,7 +1663,7 @@ impl<'p> Parser<'p> {
         Ok(())
     }
 
-    fn next(&mut self) -> Result<(), ()> {
+    fn next(&mut self) -> Result<(), Diagnostic> {
         if self.index <= self.tokens.len() {
             self.index += 1;
             Ok(())
@@ -1395,14 +1711,14 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn eat(&mut self) -> Result<String, ()> {
+    fn eat(&mut self) -> Result<String, Diagnostic> {
         let lexeme = self.current().lexeme;
         self.next()?;
 
         Ok(lexeme)
     }
 
-    fn eat_lexeme(&mut self, lexeme: &str) -> Result<String, ()> {
+    fn eat_lexeme(&mut self, lexeme: &str) -> Result<String, Diagnostic> {
         if self.current_lexeme() == lexeme {
             let lexeme = self.current().lexeme;
             self.next()?;
@@ -1421,7 +1737,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn eat_type(&mut self, token_type: &TokenType) -> Result<String, ()> {
+    fn eat_type(&mut self, token_type: &TokenType) -> Result<String, Diagnostic> {
         if self.current_type() == *token_type {
             let lexeme = self.current().lexeme.clone();
             self.next()?;
@@ -1448,7 +1764,7 @@ impl<'p> Parser<'p> {
         self.current().token_type
     }
 
-    fn expect_type(&self, token_type: TokenType) -> Result<(), ()> {
+    fn expect_type(&self, token_type: TokenType) -> Result<(), Diagnostic> {
         if self.current_type() == token_type {
             Ok(())
         } else {
@@ -1464,7 +1780,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn expect_lexeme(&self, lexeme: &str) -> Result<(), ()> {
+    fn expect_lexeme(&self, lexeme: &str) -> Result<(), Diagnostic> {
         if self.current_lexeme() == lexeme {
             Ok(())
         } else {
@@ -1485,8 +1801,8 @@ impl<'p> Parser<'p> {
     fn parse_block_of<B>(
         &mut self,
         delimeters: (&str, &str),
-        parse_with: &dyn Fn(&mut Self) -> Result<Option<B>, ()>,
-    ) -> Result<Vec<B>, ()> {
+        parse_with: &dyn Fn(&mut Self) -> Result<Option<B>, Diagnostic>,
+    ) -> Result<Vec<B>, Diagnostic> {
         self.enter_sequence();
         self.eat_lexeme(delimeters.0)?;
 
@@ -1523,12 +1839,22 @@ impl<'p> Parser<'p> {
         if !block_tokens.is_empty() {
             let mut parser = Parser::new(block_tokens, self.source);
             parser.in_sequence = self.in_sequence;
+            parser.generics = self.generics.clone();
             let mut block = Vec::new();
 
-            while let Some(element) = parse_with(&mut parser)? {
-                block.push(element)
-            }
-            
+            let parsed = loop {
+                match parse_with(&mut parser) {
+                    Ok(Some(element)) => block.push(element),
+                    Ok(None) => break Ok(()),
+                    Err(diagnostic) => break Err(diagnostic),
+                }
+            };
+
+            // keep whatever the inner parser recovered from
+            self.diagnostics.append(&mut parser.diagnostics);
+
+            parsed?;
+
             self.exit_sequence();
 
             Ok(block)
@@ -1539,15 +1865,23 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn _parse_statement(self: &mut Self) -> Result<Option<Statement>, ()> {
-        if self.remaining() > 0 {
-            Ok(Some(self.parse_statement()?))
-        } else {
-            Ok(None)
+    // Never fails, broken statements are reported and skipped
+    fn _parse_statement(self: &mut Self) -> Result<Option<Statement>, Diagnostic> {
+        while self.remaining() > 0 {
+            match self.parse_statement() {
+                Ok(statement) => return Ok(Some(statement)),
+
+                Err(diagnostic) => {
+                    self.diagnostics.push(diagnostic);
+                    self.synchronize()
+                }
+            }
         }
+
+        Ok(None)
     }
 
-    fn _parse_expression(self: &mut Self) -> Result<Option<Expression>, ()> {
+    fn _parse_expression(self: &mut Self) -> Result<Option<Expression>, Diagnostic> {
         let expression = self.parse_expression()?;
 
         match expression.node {
@@ -1556,7 +1890,7 @@ impl<'p> Parser<'p> {
         }
     }
 
-    fn _parse_name_comma(self: &mut Self) -> Result<Option<String>, ()> {
+    fn _parse_name_comma(self: &mut Self) -> Result<Option<String>, Diagnostic> {
         if self.remaining() == 0 {
             Ok(None)
         } else {
@@ -1590,7 +1924,7 @@ impl<'p> Parser<'p> {
     }
 
     // Static method for parsing sequence `expr* ,* \n*` - for things like [1, 2, 3, 4,]
-    fn _parse_expression_comma(self: &mut Self) -> Result<Option<Expression>, ()> {
+    fn _parse_expression_comma(self: &mut Self) -> Result<Option<Expression>, Diagnostic> {
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
             self.next()?;
             self.next_newline()?
@@ -1615,7 +1949,7 @@ impl<'p> Parser<'p> {
         expression
     }
 
-    fn _parse_param_comma(self: &mut Self) -> Result<Option<(String, Type)>, ()> {
+    fn _parse_param_comma(self: &mut Self) -> Result<Option<(String, Type)>, Diagnostic> {
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
             self.next()?
         }
@@ -1704,7 +2038,7 @@ impl<'p> Parser<'p> {
         Ok(param)
     }
 
-    fn _parse_definition_comma(self: &mut Self) -> Result<Option<(String, Expression)>, ()> {
+    fn _parse_definition_comma(self: &mut Self) -> Result<Option<(String, Expression)>, Diagnostic> {
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
             self.next()?
         }
@@ -1747,7 +2081,7 @@ impl<'p> Parser<'p> {
         Ok(param)
     }
 
-    fn _parse_struct_param_comma(self: &mut Self) -> Result<Option<(String, Type)>, ()> {
+    fn _parse_struct_param_comma(self: &mut Self) -> Result<Option<(String, Type)>, Diagnostic> {
         if self.remaining() > 0 && self.current_lexeme() == "\n" {
             self.next()?;
             self.next_newline()?;
@@ -1786,7 +2120,100 @@ impl<'p> Parser<'p> {
         Ok(param)
     }
 
-    fn _parse_type_comma(self: &mut Self) -> Result<Option<Type>, ()> {
+    fn _parse_pattern_comma(self: &mut Self) -> Result<Option<Pattern>, Diagnostic> {
+        self.next_newline()?;
+
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        let pattern = self.parse_pattern()?;
+
+        self.next_newline()?;
+
+        if self.remaining() > 0 {
+            self.eat_lexeme(",")?;
+
+            self.next_newline()?
+        }
+
+        Ok(Some(pattern))
+    }
+
+    fn _parse_field_pattern_comma(self: &mut Self) -> Result<Option<(String, Pattern)>, Diagnostic> {
+        self.next_newline()?;
+
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        let position = self.current_position();
+
+        let name = self.eat_type(&TokenType::Identifier)?;
+
+        // `x` alone binds the field to a name of its own
+        let pattern = if self.current_lexeme() == ":" {
+            self.next()?;
+
+            self.parse_pattern()?
+        } else {
+            Pattern::new(PatternNode::Binding(name.clone()), position)
+        };
+
+        self.next_newline()?;
+
+        if self.remaining() > 0 {
+            self.eat_lexeme(",")?;
+
+            self.next_newline()?
+        }
+
+        Ok(Some((name, pattern)))
+    }
+
+    fn _parse_variant_comma(self: &mut Self) -> Result<Option<(String, Vec<Type>)>, Diagnostic> {
+        if self.remaining() > 0 && self.current_lexeme() == "\n" {
+            self.next()?;
+            self.next_newline()?;
+        }
+
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+
+        let name = self.eat_type(&TokenType::Identifier)?;
+
+        let payload = if self.current_lexeme() == "(" {
+            self.parse_block_of(("(", ")"), &Self::_parse_type_comma)?
+        } else {
+            Vec::new()
+        };
+
+        let variant = Some((name, payload));
+
+        if self.remaining() > 0 {
+            if ![",", "\n"].contains(&self.current_lexeme().as_str()) {
+                return Err(response!(
+                    Wrong(format!(
+                        "expected `,` or newline, found `{}`",
+                        self.current_lexeme()
+                    )),
+                    self.source.file,
+                    self.current_position()
+                ));
+            } else {
+                self.next()?;
+            }
+
+            if self.remaining() > 0 && self.current_lexeme() == "\n" {
+                self.next()?
+            }
+        }
+
+        Ok(variant)
+    }
+
+    fn _parse_type_comma(self: &mut Self) -> Result<Option<Type>, Diagnostic> {
         if self.remaining() == 0 {
             Ok(None)
         } else {
@@ -0,0 +1,135 @@
+use colored::Colorize;
+
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+
+use super::driver::{CompileOptions, Session};
+use super::error::{Emitter, Terminal};
+
+const INTRO: &str = "\
+Type Wu to see what it infers, declarations stick around.
+    :lua   # Toggle showing the generated Lua
+    :quit  # Leave, as does ctrl-d
+";
+
+pub fn repl(show_lua: bool) {
+    let mut show_lua = show_lua;
+
+    let mut editor = Editor::<()>::new();
+    let mut session = Session::new(CompileOptions::default());
+
+    println!("{}", INTRO);
+
+    let mut input = String::new();
+
+    loop {
+        let prompt = if input.is_empty() { ">> " } else { ".. " };
+
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+
+            // ctrl-c throws away whatever is half-written
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            }
+
+            Err(_) => break,
+        };
+
+        if input.is_empty() {
+            match line.trim() {
+                "" => continue,
+
+                ":quit" => break,
+
+                ":lua" => {
+                    show_lua = !show_lua;
+
+                    println!("{} generated Lua", if show_lua { "showing" } else { "hiding" });
+                    continue;
+                }
+
+                _ => (),
+            }
+        }
+
+        input.push_str(&line);
+        input.push('\n');
+
+        // keep reading until blocks are closed
+        if depth(&input) > 0 {
+            continue;
+        }
+
+        editor.add_history_entry(input.trim_end());
+
+        match session.eval(&input) {
+            Ok(evaluated) => {
+                for warning in evaluated.warnings.iter() {
+                    Terminal.emit(warning)
+                }
+
+                for (name, kind) in evaluated.bindings.iter() {
+                    println!("{}: {}", name, format!("{}", kind).cyan().bold())
+                }
+
+                if let Some(kind) = evaluated.kind {
+                    println!("{}", format!("{}", kind).cyan().bold())
+                }
+
+                if show_lua {
+                    println!("{}", evaluated.lua.trim_end().dimmed())
+                }
+            }
+
+            Err(diagnostics) => {
+                for diagnostic in diagnostics.iter() {
+                    Terminal.emit(diagnostic)
+                }
+
+                println!()
+            }
+        }
+
+        input.clear()
+    }
+}
+
+// How many brackets are left open, not counting those in strings and comments
+fn depth(input: &str) -> isize {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut comment = false;
+
+    for c in input.chars() {
+        if comment {
+            comment = c != '\n';
+
+            continue;
+        }
+
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false
+            } else if c == '\\' {
+                escaped = true
+            } else if c == q {
+                quote = None
+            }
+
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '#' => comment = true,
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    depth
+}
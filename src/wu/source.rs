@@ -5,7 +5,7 @@ use std::fmt;
 
 use colored::Colorize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FilePath(pub String);
 
 impl fmt::Display for FilePath {
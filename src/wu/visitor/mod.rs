@@ -1,6 +1,7 @@
 pub mod symtab;
 pub mod visitor;
 
+use super::error::Diagnostic;
 use super::lexer::*;
 use super::parser::*;
 use super::source::*;
@@ -1,23 +1,27 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::visitor::*;
 
 #[derive(Debug, Clone)]
 pub struct Frame {
     pub table: RefCell<HashMap<String, Type>>,
+    // names that stand in for a declaration that failed
+    pub poisoned: RefCell<HashSet<String>>,
 }
 
 impl Frame {
     pub fn new() -> Self {
         Frame {
             table: RefCell::new(HashMap::new()),
+            poisoned: RefCell::new(HashSet::new()),
         }
     }
 
     pub fn from(table: HashMap<String, Type>) -> Self {
         Frame {
             table: RefCell::new(table),
+            poisoned: RefCell::new(HashSet::new()),
         }
     }
 
@@ -30,9 +34,15 @@ impl Frame {
     }
 
     pub fn assign(&mut self, name: String, t: Type) {
+        self.poisoned.borrow_mut().remove(&name);
         self.table.borrow_mut().insert(name, t);
     }
 
+    pub fn poison(&mut self, name: String, t: Type) {
+        self.table.borrow_mut().insert(name.clone(), t);
+        self.poisoned.borrow_mut().insert(name);
+    }
+
     #[allow(dead_code)]
     pub fn debug(&self) {
         println!("======= frame");
@@ -82,6 +92,19 @@ impl SymTab {
         self.current_frame_mut().assign(name.to_string(), t)
     }
 
+    pub fn poison(&mut self, name: String, t: Type) {
+        self.current_frame_mut().poison(name, t)
+    }
+
+    // Whether `name` is the stand-in of a failed declaration, rather than something declared since
+    pub fn is_poisoned(&self, name: &String) -> bool {
+        self.stack
+            .iter()
+            .rev()
+            .find(|frame| frame.table.borrow().contains_key(name))
+            .is_some_and(|frame| frame.poisoned.borrow().contains(name))
+    }
+
     pub fn fetch(&self, name: &String) -> Option<Type> {
         let mut offset = self.stack.len() - 1;
 
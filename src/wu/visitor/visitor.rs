@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::fmt::{self, Display, Formatter};
 use std::rc::Rc;
 
+use super::super::error::{dedup, Label};
 use super::super::error::Response::*;
 
 use super::*;
@@ -25,10 +26,13 @@ pub enum TypeNode {
     Array(Rc<Type>, Option<usize>),
     Func(Vec<Type>, Rc<Type>, Option<Rc<ExpressionNode>>, bool),
     Module(HashMap<String, Type>, bool), // is_foreign
-    Struct(String, HashMap<String, Type>, String),
+    Struct(String, HashMap<String, Type>, String, Vec<(String, Type)>), // type parameters and what they stand for
     Trait(String, HashMap<String, Type>),
+    Enum(String, Vec<(String, Vec<Type>)>, String),
     Optional(Rc<TypeNode>),
     Tuple(Vec<Type>),
+    Generic(String, Vec<Type>), // bounds: Vec<Type>
+    Applied(Rc<Expression>, Vec<Type>), // `Stack<int>`, until it's deid'd
     This,
 }
 
@@ -83,19 +87,136 @@ impl TypeNode {
             (&Tuple(ref a), &Tuple(ref b)) => a == b,
             (&Optional(ref a), &Optional(ref b)) => a == b,
             (&Id(ref a), &Id(ref b)) => a == b,
+            (&Generic(ref a, _), &Generic(ref b, _)) => a == b,
             (&Array(ref a, ref la), &Array(ref b, ref lb)) => a == b && (la == &None || la == lb),
             (&Func(ref a_params, ref a_retty, .., a), &Func(ref b_params, ref b_retty, .., b)) => {
                 a_params == b_params && a_retty == b_retty && a == b
             }
-            (&Struct(ref name, _, ref content), &Struct(ref name_b, _, ref content_b)) => {
-                name == name_b && content == content_b
-            }
+            (
+                &Struct(ref name, _, ref content, ref params),
+                &Struct(ref name_b, _, ref content_b, ref params_b),
+            ) => name == name_b && content == content_b && params == params_b,
             (&Trait(ref name, ref content), &Trait(ref name_b, ref content_b)) => {
                 name == name_b && content == content_b
             }
+            (&Enum(_, _, ref id), &Enum(_, _, ref id_b)) => id == id_b,
             _ => false,
         }
     }
+
+    // Binds the generics in `self` to what they line up with in `other`, first come first served
+    pub fn infer(&self, other: &TypeNode, bindings: &mut HashMap<String, Type>) {
+        use self::TypeNode::*;
+
+        match (self, other) {
+            (Generic(name, _), _) if !bindings.contains_key(name) => {
+                bindings.insert(name.clone(), Type::from(other.clone()));
+            }
+
+            (Array(a, _), Array(b, _)) => a.node.infer(&b.node, bindings),
+            (Optional(a), Optional(b)) => a.infer(b, bindings),
+            (Optional(_), Nil) => (),
+            (Optional(a), _) => a.infer(other, bindings),
+
+            (Tuple(a), Tuple(b)) => {
+                for (a, b) in a.iter().zip(b.iter()) {
+                    a.node.infer(&b.node, bindings)
+                }
+            }
+
+            (Func(a_params, a_retty, ..), Func(b_params, b_retty, ..)) => {
+                for (a, b) in a_params.iter().zip(b_params.iter()) {
+                    a.node.infer(&b.node, bindings)
+                }
+
+                a_retty.node.infer(&b_retty.node, bindings)
+            }
+
+            (Struct(_, _, id, params), Struct(_, _, id_b, params_b)) if id == id_b => {
+                for (a, b) in params.iter().zip(params_b.iter()) {
+                    a.1.node.infer(&b.1.node, bindings)
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    // The type with its generics swapped for what they're bound to
+    pub fn substitute(&self, bindings: &HashMap<String, Type>) -> TypeNode {
+        use self::TypeNode::*;
+
+        let substitute = |t: &Type| Type::new(t.node.substitute(bindings), t.mode.clone());
+
+        match *self {
+            Generic(ref name, _) => match bindings.get(name) {
+                Some(t) => t.node.clone(),
+                None => self.clone(),
+            },
+
+            Array(ref t, len) => Array(Rc::new(substitute(t)), len),
+            Optional(ref t) => Optional(Rc::new(t.substitute(bindings))),
+            Tuple(ref content) => Tuple(content.iter().map(substitute).collect()),
+
+            Func(ref params, ref retty, ref func, is_method) => Func(
+                params.iter().map(substitute).collect(),
+                Rc::new(substitute(retty)),
+                func.clone(),
+                is_method,
+            ),
+
+            Struct(ref name, ref content, ref id, ref params) => Struct(
+                name.clone(),
+                content
+                    .iter()
+                    .map(|(key, t)| (key.clone(), substitute(t)))
+                    .collect(),
+                id.clone(),
+                params
+                    .iter()
+                    .map(|(key, t)| (key.clone(), substitute(t)))
+                    .collect(),
+            ),
+
+            _ => self.clone(),
+        }
+    }
+
+    // The generics found in the type, with their bounds
+    pub fn generics(&self, found: &mut Vec<(String, Vec<Type>)>) {
+        use self::TypeNode::*;
+
+        match *self {
+            Generic(ref name, ref bounds) if !found.iter().any(|generic| &generic.0 == name) => {
+                found.push((name.clone(), bounds.clone()))
+            }
+
+            Array(ref t, _) => t.node.generics(found),
+            Optional(ref t) => t.generics(found),
+
+            Tuple(ref content) => {
+                for t in content.iter() {
+                    t.node.generics(found)
+                }
+            }
+
+            Func(ref params, ref retty, ..) => {
+                for t in params.iter() {
+                    t.node.generics(found)
+                }
+
+                retty.node.generics(found)
+            }
+
+            Struct(.., ref params) => {
+                for (_, t) in params.iter() {
+                    t.node.generics(found)
+                }
+            }
+
+            _ => (),
+        }
+    }
 }
 
 impl PartialEq for TypeNode {
@@ -120,15 +241,18 @@ impl PartialEq for TypeNode {
                 a == b && (la == &None || (a.node == Any && lb == &None) || la == lb)
             }
             (&Id(ref a), &Id(ref b)) => a == b,
+            (&Generic(ref a, _), &Generic(ref b, _)) => a == b,
             (&Func(ref a_params, ref a_retty, .., a), &Func(ref b_params, ref b_retty, .., b)) => {
                 a_params == b_params && a_retty == b_retty && a == b
             }
 
-            (&Struct(ref name, _, ref content), &Struct(ref name_b, _, ref content_b)) => {
-                name == name_b && content == content_b
-            }
+            (
+                &Struct(ref name, _, ref content, ref params),
+                &Struct(ref name_b, _, ref content_b, ref params_b),
+            ) => name == name_b && content == content_b && params == params_b,
             (&Trait(_, ref content), &Trait(_, ref content_b)) => content == content_b,
-            (&Trait(_, ref content), &Struct(_, ref content_b, _)) => {
+            (&Enum(_, _, ref id), &Enum(_, _, ref id_b)) => id == id_b,
+            (&Trait(_, ref content), &Struct(_, ref content_b, ..)) => {
                 for (name, ty) in content.iter() {
                     if let Some(ty_b) = content_b.get(name) {
                         if ty.node != ty_b.node {
@@ -227,9 +351,30 @@ impl Display for TypeNode {
             }
 
             Id(ref n) => write!(f, "deid({})", n.pos.get_lexeme()),
+            Generic(ref name, _) => write!(f, "{}", name),
+            Applied(ref n, _) => write!(f, "deid({})", n.pos.get_lexeme()),
 
             Module(..) => write!(f, "module"),
-            Struct(ref name, ..) => write!(f, "{}", name),
+            Struct(ref name, _, _, ref params) => {
+                write!(f, "{}", name)?;
+
+                if !params.is_empty() {
+                    write!(f, "<")?;
+
+                    for (i, param) in params.iter().enumerate() {
+                        write!(f, "{}", param.1)?;
+
+                        if i != params.len() - 1 {
+                            write!(f, ", ")?;
+                        }
+                    }
+
+                    write!(f, ">")?;
+                }
+
+                Ok(())
+            }
+            Enum(ref name, ..) => write!(f, "{}", name),
 
             Func(ref params, ref return_type, ..) => {
                 write!(f, "fun(")?;
@@ -364,18 +509,37 @@ pub struct Visitor<'v> {
     pub inside: Vec<Inside>,
 
     pub method_calls: HashMap<Pos, bool>,
+    // bare names in `switch` arms that turned out to be variants
+    pub variant_patterns: HashMap<Pos, bool>,
     pub module_content: HashMap<String, Type>,
     pub import_map: HashMap<Pos, (String, String)>,
+    // the file each import statement resolved to
+    pub modules: HashMap<Pos, String>,
 
     pub root: String,
     pub is_deep: bool,
+
+    pub diagnostics: Vec<Diagnostic>,
+    // where bindings standing in for failed declarations were used
+    poisoned: Vec<Pos>,
 }
 
 impl<'v> Visitor<'v> {
-    pub fn visit(&mut self) -> Result<(), ()> {
-        self.visit_block(self.ast, false, true)?;
+    // Hands back the warnings when nothing was wrong
+    pub fn visit(&mut self) -> Result<Vec<Diagnostic>, Vec<Diagnostic>> {
+        if let Err(diagnostic) = self.visit_block(self.ast, false, true) {
+            self.diagnostics.push(diagnostic)
+        }
 
-        Ok(())
+        let mut diagnostics = self.diagnostics.drain(..).collect();
+
+        dedup(&mut diagnostics);
+
+        if diagnostics.iter().any(Diagnostic::is_wrong) {
+            Err(diagnostics)
+        } else {
+            Ok(diagnostics)
+        }
     }
 
     pub fn new(ast: &'v Vec<Statement>, source: &'v Source, root: String) -> Self {
@@ -389,11 +553,16 @@ impl<'v> Visitor<'v> {
             inside: Vec::new(),
 
             method_calls: HashMap::new(),
+            variant_patterns: HashMap::new(),
             module_content: HashMap::new(),
             import_map: HashMap::new(),
+            modules: HashMap::new(),
 
             root,
             is_deep: false,
+
+            diagnostics: Vec::new(),
+            poisoned: Vec::new(),
         }
     }
 
@@ -414,15 +583,20 @@ impl<'v> Visitor<'v> {
             inside: Vec::new(),
 
             method_calls: HashMap::new(),
+            variant_patterns: HashMap::new(),
             module_content: HashMap::new(),
             import_map: HashMap::new(),
+            modules: HashMap::new(),
 
             root,
             is_deep: false,
+
+            diagnostics: Vec::new(),
+            poisoned: Vec::new(),
         }
     }
 
-    fn visit_statement(&mut self, statement: &Statement) -> Result<(), ()> {
+    fn visit_statement(&mut self, statement: &Statement) -> Result<(), Diagnostic> {
         use self::StatementNode::*;
 
         match statement.node {
@@ -490,6 +664,8 @@ impl<'v> Visitor<'v> {
                 // &self.root.clone()
                 let module = self.find_module(path, &local_root, &statement, self.is_deep)?;
 
+                self.modules.insert(statement.pos.clone(), module.clone());
+
                 let mut file = match File::open(&module) {
                     Err(why) => panic!("failed to open {}: {}", module, why),
                     Ok(file) => file,
@@ -506,14 +682,12 @@ impl<'v> Visitor<'v> {
                         let mut tokens = Vec::new();
 
                         for token_result in lexer {
-                            if let Ok(token) = token_result {
-                                tokens.push(token)
-                            } else {
-                                panic!("weird unexpected lexer error")
-                            }
+                            tokens.push(token_result?)
                         }
 
-                        let parsed = Parser::new(tokens, &source).parse()?;
+                        let parsed = Parser::new(tokens, &source)
+                            .parse()
+                            .map_err(|diagnostics| self.absorb(diagnostics))?;
 
                         let mut is_deep = false;
 
@@ -531,7 +705,10 @@ impl<'v> Visitor<'v> {
                         let mut visitor = Visitor::new(&parsed, &source, root);
                         visitor.is_deep = is_deep;
 
-                        visitor.visit()?;
+                        // its warnings are for when it's compiled itself
+                        visitor
+                            .visit()
+                            .map_err(|diagnostics| self.absorb(diagnostics))?;
 
                         let content_type = visitor.module_content.clone();
 
@@ -584,7 +761,7 @@ impl<'v> Visitor<'v> {
 
                         self.inside.push(Inside::Implement(kind.clone()));
 
-                        if let TypeNode::Struct(struct_name, content, id) = kind.node.clone() {
+                        if let TypeNode::Struct(struct_name, content, id, _) = kind.node.clone() {
                             if kind.mode.strong_cmp(&TypeMode::Undeclared) {
                                 let new_content = content;
 
@@ -612,11 +789,11 @@ impl<'v> Visitor<'v> {
                                 if let Some(ref expr) = parent {
                                     let trait_ty = self.type_expression(expr)?;
 
-                                    if let TypeNode::Struct(_, ref content, _) =
+                                    if let TypeNode::Struct(_, ref content, ..) =
                                         self.fetch(&struct_name, &position)?.node
                                     {
                                         if let TypeNode::Trait(ref _n, ref content_b) = trait_ty.node {
-                                            if let TypeNode::Struct(_, _, _) = trait_ty.node {
+                                            if let TypeNode::Struct(..) = trait_ty.node {
                                                 //TODO: isn't this impossible? Should this be
                                                 //nested differently?
                                                 return Err(response!(
@@ -661,8 +838,18 @@ impl<'v> Visitor<'v> {
                                 self.source.file,
                                 position
                             ))
+                        } else if let TypeNode::Any = kind.node {
+                            // already reported where the struct went wrong
+                            self.inside.pop();
+                            self.pop_scope();
+
+                            Ok(())
                         } else {
-                            unreachable!()
+                            Err(response!(
+                                Wrong(format!("can't implement type `{}`", kind)),
+                                self.source.file,
+                                position
+                            ))
                         }
                     },
 
@@ -675,7 +862,7 @@ impl<'v> Visitor<'v> {
                                 TypeNode::Module(ref module_content, _is_foreign) => {
                                     if let Identifier(ref name) = indexing.node {
                                         if let Some(ref kind) = module_content.get(name) {
-                                            if let TypeNode::Struct(name, content, id) =
+                                            if let TypeNode::Struct(name, content, id, _) =
                                                 kind.node.clone()
                                             {
                                                 if kind.mode.strong_cmp(&TypeMode::Undeclared) {
@@ -703,7 +890,7 @@ impl<'v> Visitor<'v> {
                                                         let trait_ty =
                                                             self.type_expression(expr)?;
 
-                                                        if let TypeNode::Struct(_, ref content, _) =
+                                                        if let TypeNode::Struct(_, ref content, ..) =
                                                             self.type_expression(&struct_name)?.node
                                                         {
                                                             if let TypeNode::Trait(
@@ -817,7 +1004,7 @@ impl<'v> Visitor<'v> {
         }
     }
 
-    fn visit_expression(&mut self, expression: &Expression) -> Result<(), ()> {
+    fn visit_expression(&mut self, expression: &Expression) -> Result<(), Diagnostic> {
         use self::ExpressionNode::*;
 
         match expression.node {
@@ -917,7 +1104,17 @@ impl<'v> Visitor<'v> {
             Initialization(ref left, ref args) => {
                 let struct_type = self.type_expression(&*left)?;
 
-                if let TypeNode::Struct(ref name, ref content, ref struct_id) = struct_type.node {
+                if let TypeNode::Any = struct_type.node {
+                    for arg in args.iter() {
+                        self.visit_expression(&arg.1)?
+                    }
+
+                    return Ok(());
+                }
+
+                let struct_type = self.instance(struct_type, args)?;
+
+                if let TypeNode::Struct(ref name, ref content, ref struct_id, _) = struct_type.node {
                     if struct_type.mode.strong_cmp(&TypeMode::Undeclared) {
                         let mut validation_map = HashMap::new();
 
@@ -1050,7 +1247,11 @@ impl<'v> Visitor<'v> {
                                         body_type, else_body_type
                                     )),
                                     self.source.file,
-                                    body.pos
+                                    body.pos,
+                                    Label::new(
+                                        condition.pos.clone(),
+                                        format!("first branch is `{}`", body_type)
+                                    )
                                 ));
                             }
                         }
@@ -1236,7 +1437,11 @@ impl<'v> Visitor<'v> {
                                 t, element_type
                             )),
                             self.source.file,
-                            element.pos
+                            element.pos,
+                            Label::new(
+                                content.first().unwrap().pos.clone(),
+                                "expected because of this"
+                            )
                         ));
                     }
                 }
@@ -1244,7 +1449,7 @@ impl<'v> Visitor<'v> {
                 Ok(())
             }
 
-            Struct(_, ref params, _) => {
+            Struct(_, ref params, ..) => {
                 let mut name_buffer = Vec::new();
 
                 for &(ref name, _) in params.iter() {
@@ -1280,12 +1485,113 @@ impl<'v> Visitor<'v> {
                 Ok(())
             }
 
+            Enum(_, ref variants, _) => {
+                let mut name_buffer = Vec::new();
+
+                for &(ref name, _) in variants.iter() {
+                    if name_buffer.contains(&name) {
+                        return Err(response!(
+                            Wrong(format!("variant `{}` defined more than once", name)),
+                            self.source.file,
+                            expression.pos
+                        ));
+                    }
+
+                    name_buffer.push(&name)
+                }
+
+                Ok(())
+            }
+
+            Switch(ref subject, ref arms) => {
+                self.visit_expression(subject)?;
+
+                let subject_type = self.type_expression(subject)?;
+
+                let mut first: Option<(Type, Pos)> = None;
+                let mut covered = Vec::new();
+
+                for (pattern, guard, body) in arms.iter() {
+                    self.push_scope();
+
+                    self.visit_pattern(pattern, &subject_type, false)?;
+
+                    if let Some(ref guard) = *guard {
+                        self.visit_expression(guard)?;
+
+                        let guard_type = self.type_expression(guard)?;
+
+                        if guard_type.node != TypeNode::Bool {
+                            return Err(response!(
+                                Wrong(format!(
+                                    "mismatched condition, must be `bool` got `{}`",
+                                    guard_type
+                                )),
+                                self.source.file,
+                                guard.pos
+                            ));
+                        }
+                    } else {
+                        covered.append(&mut self.covered_variants(pattern, &subject_type))
+                    }
+
+                    self.visit_expression(body)?;
+
+                    let body_type = self.type_expression(body)?;
+
+                    self.pop_scope();
+
+                    if let Some((ref first_type, ref first_pos)) = first {
+                        if body_type != *first_type {
+                            return Err(response!(
+                                Wrong(format!(
+                                    "mismatched types, expected `{}` got `{}`",
+                                    first_type, body_type
+                                )),
+                                self.source.file,
+                                body.pos,
+                                Label::new(
+                                    first_pos.clone(),
+                                    format!("first arm is `{}`", first_type)
+                                )
+                            ));
+                        }
+                    } else {
+                        first = Some((body_type, pattern.pos.clone()))
+                    }
+                }
+
+                if let TypeNode::Enum(ref name, ref variants, _) = subject_type.node {
+                    let missing = variants
+                        .iter()
+                        .filter(|variant| !covered.contains(&variant.0))
+                        .map(|variant| format!("`{}`", variant.0))
+                        .collect::<Vec<String>>();
+
+                    if !missing.is_empty() {
+                        return Err(response!(
+                            Wrong(format!(
+                                "non-exhaustive switch over `{}`, missing {}",
+                                name,
+                                missing.join(", ")
+                            )),
+                            self.source.file,
+                            expression.pos,
+                            Note("arms with an `if` guard don't count, `_` matches the rest")
+                        ));
+                    }
+                }
+
+                Ok(())
+            }
+
             Call(ref expr, ref args) => {
                 self.visit_expression(expr)?;
 
                 self.inside.push(Inside::Calling(expr.pos.clone()));
 
                 let expression_type = self.type_expression(expr)?;
+                let expression_type = self.instantiate(expression_type, args)?;
 
                 if let TypeNode::Func(ref params, _, ref _func, .., is_method) = expression_type.node
                 {
@@ -1431,7 +1737,7 @@ impl<'v> Visitor<'v> {
                     let ident_type = self.type_expression(&ident)?;
 
                     match ident_type.node {
-                        TypeNode::Struct(..) | TypeNode::Trait(..) => {
+                        TypeNode::Struct(..) | TypeNode::Trait(..) | TypeNode::Enum(..) => {
                             return_type = Type::from(ident_type.node)
                         }
                         _ => {
@@ -1464,6 +1770,17 @@ impl<'v> Visitor<'v> {
                     frame_hash.insert(param.0.clone(), self.deid(param.1.clone())?);
                 }
 
+                // bounds have to be traits, whether or not the function is ever called
+                let mut generics = Vec::new();
+
+                for param in params.iter() {
+                    param.1.node.generics(&mut generics)
+                }
+
+                for (_, bounds) in generics.iter() {
+                    self.bounds(bounds)?;
+                }
+
                 if *is_method {
                     let mut found = false;
 
@@ -1510,7 +1827,8 @@ impl<'v> Visitor<'v> {
             }
 
             Index(ref left, ref index, _) => {
-                let mut left_type = self.type_expression(left)?;
+                let left_type = self.type_expression(left)?;
+                let mut left_type = self.bounded(left_type)?;
 
                 if let TypeMode::Splat(_) = left_type.mode {
                     left_type = Type::from(TypeNode::Array(
@@ -1588,7 +1906,7 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
-                    TypeNode::Struct(_, ref content, ref id) => {
+                    TypeNode::Struct(_, ref content, ref id, _) => {
                         self.inside.push(Inside::Implement(left_type.clone()));
 
                         if let Identifier(ref name) = index.node {
@@ -1630,6 +1948,37 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
+                    TypeNode::Enum(ref enum_name, ref variants, _) => {
+                        if !left_type.mode.strong_cmp(&TypeMode::Undeclared) {
+                            return Err(response!(
+                                Wrong(format!("can't index `{}`, `switch` on it instead", left_type)),
+                                self.source.file,
+                                left.pos
+                            ));
+                        }
+
+                        if let Identifier(ref name) = index.node {
+                            if !variants.iter().any(|variant| &variant.0 == name) {
+                                return Err(response!(
+                                    Wrong(format!(
+                                        "no such variant `{}` in enum `{}`",
+                                        name, enum_name
+                                    )),
+                                    self.source.file,
+                                    index.pos
+                                ));
+                            }
+                        } else {
+                            let index_type = self.type_expression(index)?;
+
+                            return Err(response!(
+                                Wrong(format!("can't index enum with `{}`", index_type)),
+                                self.source.file,
+                                index.pos
+                            ));
+                        }
+                    }
+
                     TypeNode::Any => (),
 
                     _ => {
@@ -1653,7 +2002,7 @@ impl<'v> Visitor<'v> {
         variable: &StatementNode,
         pos: &Pos,
         is_splat: bool,
-    ) -> Result<(), ()> {
+    ) -> Result<(), Diagnostic> {
         use self::ExpressionNode::*;
 
         if let &StatementNode::Variable(ref var_type, ref name, ref right, _) = variable {
@@ -1667,6 +2016,10 @@ impl<'v> Visitor<'v> {
 
             let mut variable_type = var_type.clone();
 
+            if let TypeNode::Applied(..) = var_type.node {
+                variable_type = self.deid(var_type.clone())?
+            }
+
             if let TypeNode::Id(ref ident) = var_type.node {
                 let mut ident_type = self.type_expression(&ident)?;
 
@@ -1674,7 +2027,7 @@ impl<'v> Visitor<'v> {
                     ident_type.mode = TypeMode::Regular
                 }
 
-                if let TypeNode::Struct(..) = ident_type.node {
+                if let TypeNode::Struct(..) | TypeNode::Enum(..) = ident_type.node {
                     variable_type = Type::from(ident_type.node)
                 } else {
                     return Err(response!(
@@ -1690,7 +2043,7 @@ impl<'v> Visitor<'v> {
             if let &Some(ref right) = right {
                 match right.node {
                     Function(..) | Block(_) | If(..) | While(..) | For(..) => (),
-                    Struct(..) | Trait(..) => {
+                    Struct(..) | Trait(..) | Enum(..) => {
                         self.assign(name.to_owned(), Type::from(TypeNode::Any)) // temp
                     }
                     _ => self.visit_expression(right)?,
@@ -1724,9 +2077,8 @@ impl<'v> Visitor<'v> {
                 }
 
                 match right.node {
-                    Function(..) | Block(_) | If(..) | While(..) | For(..) | Struct(..) | Trait(..) => {
-                        self.visit_expression(right)?
-                    }
+                    Function(..) | Block(_) | If(..) | While(..) | For(..) | Struct(..) | Trait(..)
+                    | Enum(..) => self.visit_expression(right)?,
                     _ => (),
                 }
             } else {
@@ -1739,7 +2091,7 @@ impl<'v> Visitor<'v> {
         }
     }
 
-    pub fn type_statement(&mut self, statement: &Statement) -> Result<Type, ()> {
+    pub fn type_statement(&mut self, statement: &Statement) -> Result<Type, Diagnostic> {
         use self::StatementNode::*;
 
         let t = match statement.node {
@@ -1757,7 +2109,7 @@ impl<'v> Visitor<'v> {
         Ok(t)
     }
 
-    fn type_expression(&mut self, expression: &Expression) -> Result<Type, ()> {
+    pub fn type_expression(&mut self, expression: &Expression) -> Result<Type, Diagnostic> {
         use self::ExpressionNode::*;
 
         let t = match expression.node {
@@ -1843,11 +2195,15 @@ impl<'v> Visitor<'v> {
                 Type::array(kind, Some(content.len()))
             }
 
-            Initialization(ref name, _) => Type::from(self.type_expression(name)?.node),
+            Initialization(ref name, ref args) => {
+                let struct_type = self.type_expression(name)?;
+
+                Type::from(self.instance(struct_type, args)?.node)
+            }
 
             If(_, ref body, ..) => self.type_expression(body)?,
 
-            Struct(ref name, ref params, ref id) => {
+            Struct(ref name, ref params, ref id, ref generics) => {
                 let mut param_hash = HashMap::new();
 
                 for param in params {
@@ -1857,8 +2213,18 @@ impl<'v> Visitor<'v> {
                     );
                 }
 
+                let generics = generics
+                    .iter()
+                    .map(|(name, bounds)| {
+                        (
+                            name.clone(),
+                            Type::from(TypeNode::Generic(name.clone(), bounds.clone())),
+                        )
+                    })
+                    .collect();
+
                 Type::new(
-                    TypeNode::Struct(name.to_owned(), param_hash, id.to_string()),
+                    TypeNode::Struct(name.to_owned(), param_hash, id.to_string(), generics),
                     TypeMode::Undeclared,
                 )
             }
@@ -1876,8 +2242,46 @@ impl<'v> Visitor<'v> {
                 Type::from(TypeNode::Trait(name.to_owned(), param_hash))
             }
 
+            Enum(ref name, ref variants, ref id) => {
+                let mut variant_types = Vec::new();
+
+                for (variant, payload) in variants {
+                    let mut payload_types = Vec::new();
+
+                    for kind in payload {
+                        payload_types.push(Type::from(self.deid(kind.clone())?.node))
+                    }
+
+                    variant_types.push((variant.clone(), payload_types))
+                }
+
+                Type::new(
+                    TypeNode::Enum(name.to_owned(), variant_types, id.to_string()),
+                    TypeMode::Undeclared,
+                )
+            }
+
+            Switch(ref subject, ref arms) => {
+                let subject_type = self.type_expression(subject)?;
+
+                if let Some((pattern, _, body)) = arms.first() {
+                    self.push_scope();
+
+                    self.visit_pattern(pattern, &subject_type, false)?;
+
+                    let body_type = self.type_expression(body)?;
+
+                    self.pop_scope();
+
+                    body_type
+                } else {
+                    Type::from(TypeNode::Nil)
+                }
+            }
+
             Index(ref array, ref index, _) => {
-                let mut kind = self.type_expression(array)?;
+                let kind = self.type_expression(array)?;
+                let mut kind = self.bounded(kind)?;
 
                 if let TypeMode::Splat(_) = kind.mode {
                     kind = Type::from(TypeNode::Array(
@@ -1922,9 +2326,36 @@ impl<'v> Visitor<'v> {
                         }
                     }
 
-                    TypeNode::Struct(ref struct_name, ref content, ref struct_id) => {
+                    TypeNode::Enum(ref enum_name, ref variants, _) => {
+                        if let Identifier(ref name) = index.node {
+                            let value = Type::from(kind.node.clone());
+
+                            match variants.iter().find(|variant| &variant.0 == name) {
+                                Some((_, payload)) if payload.is_empty() => value,
+                                Some((_, payload)) => Type::function(payload.clone(), value, false),
+
+                                None => {
+                                    return Err(response!(
+                                        Wrong(format!(
+                                            "no such variant `{}` in enum `{}`",
+                                            name, enum_name
+                                        )),
+                                        self.source.file,
+                                        index.pos
+                                    ))
+                                }
+                            }
+                        } else {
+                            unreachable!()
+                        }
+                    }
+
+                    TypeNode::Struct(ref struct_name, ref content, ref struct_id, ref params) => {
+                        // members of a `Stack<int>` are in terms of `int`
+                        let bindings = params.iter().cloned().collect::<HashMap<String, Type>>();
+
                         if let Identifier(ref name) = index.node {
-                            if !self.is_implemented(struct_id, name) {
+                            let member = if !self.is_implemented(struct_id, name) {
                                 if let Some(kind2) = content.get(name) {
                                     if kind.mode.strong_cmp(&TypeMode::Undeclared) {
                                         if kind2.is_method() {
@@ -1956,7 +2387,9 @@ impl<'v> Visitor<'v> {
                                 }
                             } else {
                                 self.symtab.get_implementation_force(struct_id, name)
-                            }
+                            };
+
+                            Type::new(member.node.substitute(&bindings), member.mode)
                         } else {
                             unreachable!()
                         }
@@ -1972,13 +2405,14 @@ impl<'v> Visitor<'v> {
                 }
             }
 
-            Call(ref expression, _) => {
-                if let TypeNode::Func(_, ref return_type, ..) =
-                    self.type_expression(expression)?.node
-                {
-                    (**return_type).clone()
-                } else {
-                    panic!("BAM! (please submit an issue): called {:#?}", expression)
+            Call(ref expression, ref args) => {
+                let kind = self.type_expression(expression)?;
+
+                match self.instantiate(kind, args)?.node {
+                    TypeNode::Func(_, ref return_type, ..) => (**return_type).clone(),
+                    TypeNode::Any => Type::from(TypeNode::Any),
+
+                    _ => panic!("BAM! (please submit an issue): called {:#?}", expression),
                 }
             }
 
@@ -2012,7 +2446,7 @@ impl<'v> Visitor<'v> {
                     for element in statements {
                         match element.node {
                             StatementNode::Expression(ref expression) => match expression.node {
-                                Function(..) | Block(_) | If(..) | While(..) | For(..) => {
+                                Function(..) | Block(_) | If(..) | While(..) | For(..) | Switch(..) => {
                                     self.type_expression(expression)?;
                                 }
 
@@ -2265,7 +2699,11 @@ impl<'v> Visitor<'v> {
                 if let ExpressionNode::Block(ref ast) = content.node {
                     let mut visitor = Visitor::new(ast, self.source, self.root.clone());
 
-                    visitor.visit()?;
+                    let warnings = visitor
+                        .visit()
+                        .map_err(|diagnostics| self.absorb(diagnostics))?;
+
+                    self.diagnostics.extend(warnings);
 
                     let content_type = visitor.module_content.clone();
 
@@ -2314,83 +2752,179 @@ impl<'v> Visitor<'v> {
         content: &Vec<Statement>,
         ensure_implicits: bool,
         module_level: bool,
-    ) -> Result<(), ()> {
+    ) -> Result<(), Diagnostic> {
         for (i, statement) in content.iter().enumerate() {
-            let mut statement = statement.clone();
+            let snapshot = self.snapshot();
 
-            if let StatementNode::ExternBlock(ref s) = statement.node {
-                if let StatementNode::Variable(..) = s.node {
-                    statement.node = s.node.clone()
-                }
+            let is_last = i == content.len() - 1;
+
+            if let Err(diagnostic) =
+                self.visit_block_statement(statement, ensure_implicits && !is_last, module_level)
+            {
+                self.recover(diagnostic, snapshot);
+                self.poison(statement, module_level)
             }
+        }
 
-            // ommiting functions, for that extra user-feel
-            if let StatementNode::Variable(ref kind, ref name, ref value, _) = statement.node {
-                if let Some(ref right) = *value {
-                    if let ExpressionNode::Function(ref params, ref retty, .., is_method) =
-                        right.node
-                    {
-                        let mut types = Vec::new();
+        for statement in content.iter() {
+            if let StatementNode::Variable(ref _t, ref name, ref right, public) = statement.node {
+                if let Some(ref right) = *right {
+                    if let ExpressionNode::Function(..) = right.node {
+                        let snapshot = self.snapshot();
 
-                        for param in params.iter() {
-                            types.push(self.deid(param.1.clone())?)
+                        if let Err(diagnostic) = self.visit_statement(statement) {
+                            self.recover(diagnostic, snapshot);
+
+                            continue;
                         }
 
-                        let t = Type::from(TypeNode::Func(
-                            types,
-                            Rc::new(retty.clone()),
-                            Some(Rc::new(right.node.clone())),
-                            is_method,
-                        ));
+                        let t = match self.type_expression(right) {
+                            Ok(t) => t,
+                            Err(diagnostic) => {
+                                self.recover(diagnostic, snapshot);
 
-                        self.assign(name.to_owned(), t);
+                                continue;
+                            }
+                        };
 
-                        continue;
-                    } else {
-                        self.visit_statement(&statement)?;
+                        if module_level && public {
+                            self.module_content.insert(name.to_owned(), t.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-                        let t = self.type_expression(right)?;
+    fn visit_block_statement(
+        &mut self,
+        statement: &Statement,
+        ensure_implicit: bool,
+        module_level: bool,
+    ) -> Result<(), Diagnostic> {
+        let mut statement = statement.clone();
 
-                        if module_level {
-                            self.module_content.insert(name.clone(), t);
-                        }
+        if let StatementNode::ExternBlock(ref s) = statement.node {
+            if let StatementNode::Variable(..) = s.node {
+                statement.node = s.node.clone()
+            }
+        }
+
+        // ommiting functions, for that extra user-feel
+        if let StatementNode::Variable(ref kind, ref name, ref value, _) = statement.node {
+            if let Some(ref right) = *value {
+                if let ExpressionNode::Function(ref params, ref retty, .., is_method) = right.node {
+                    let mut types = Vec::new();
+
+                    for param in params.iter() {
+                        types.push(self.deid(param.1.clone())?)
                     }
+
+                    let t = Type::from(TypeNode::Func(
+                        types,
+                        Rc::new(retty.clone()),
+                        Some(Rc::new(right.node.clone())),
+                        is_method,
+                    ));
+
+                    self.assign(name.to_owned(), t);
+
+                    return Ok(());
                 } else {
+                    self.visit_statement(&statement)?;
+
+                    let t = self.type_expression(right)?;
+
                     if module_level {
-                        self.module_content.insert(name.clone(), kind.clone());
+                        self.module_content.insert(name.clone(), t);
                     }
                 }
+            } else {
+                if module_level {
+                    self.module_content.insert(name.clone(), kind.clone());
+                }
             }
+        }
 
-            if ensure_implicits {
-                if i < content.len() - 1 {
-                    if let StatementNode::Expression(ref expression) = statement.node {
-                        self.ensure_no_implicit(expression)?
-                    }
-                }
+        if ensure_implicit {
+            if let StatementNode::Expression(ref expression) = statement.node {
+                self.ensure_no_implicit(expression)?
             }
+        }
 
-            // at this point it's not a variable ...
-            self.visit_statement(&statement)?
+        // at this point it's not a variable ...
+        self.visit_statement(&statement)
+    }
+
+    fn snapshot(&self) -> (usize, usize, Option<FlagContext>) {
+        (self.symtab.stack.len(), self.inside.len(), self.flag.clone())
+    }
+
+    // Records the diagnostic and unwinds whatever scopes the failed statement left behind
+    fn recover(&mut self, diagnostic: Diagnostic, snapshot: (usize, usize, Option<FlagContext>)) {
+        if !self.blames_poison(&diagnostic) {
+            self.diagnostics.push(diagnostic)
         }
 
-        for statement in content.iter() {
-            if let StatementNode::Variable(ref _t, ref name, ref right, public) = statement.node {
-                if let Some(ref right) = *right {
-                    if let ExpressionNode::Function(..) = right.node {
-                        self.visit_statement(statement)?;
+        self.symtab.stack.truncate(snapshot.0);
+        self.inside.truncate(snapshot.1);
+        self.flag = snapshot.2;
+    }
 
-                        let t = self.type_expression(right)?;
+    // Whatever a failed statement was meant to declare becomes `any`, so
+    // the rest of the file can still be checked without repeating the error
+    fn poison(&mut self, statement: &Statement, module_level: bool) {
+        use self::StatementNode::*;
 
-                        if module_level && public {
-                            self.module_content.insert(name.to_owned(), t.clone());
-                        }
-                    }
-                }
+        let names = match statement.node {
+            Variable(_, ref name, ..) => vec![name.clone()],
+            SplatVariable(_, ref names, ..) => names.clone(),
+            Import(ref path, ref specifics, _) => {
+                let mut names = specifics.clone();
+                names.push(path.clone());
+
+                names
+            }
+            ExternBlock(ref statement) => return self.poison(statement, module_level),
+            _ => return,
+        };
+
+        for name in names {
+            self.symtab.poison(name.clone(), Type::from(TypeNode::Any));
+
+            if module_level {
+                self.module_content.insert(name, Type::from(TypeNode::Any));
             }
         }
+    }
+
+    // Whether the error points at a use of a binding that an earlier error already turned into `any`
+    fn blames_poison(&self, diagnostic: &Diagnostic) -> bool {
+        if diagnostic.file.as_ref() != Some(&self.source.file) {
+            return false;
+        }
 
-        Ok(())
+        if let Some(Pos((line, _), (start, end))) = diagnostic.span {
+            self.poisoned.iter().any(|Pos((use_line, _), (use_start, use_end))| {
+                *use_line == line && *use_start <= end && start <= *use_end
+            })
+        } else {
+            false
+        }
+    }
+
+    // Keeps the errors of a nested run, handing back the last one to propagate
+    fn absorb(&mut self, mut diagnostics: Vec<Diagnostic>) -> Diagnostic {
+        let last = match diagnostics.iter().rposition(Diagnostic::is_wrong) {
+            Some(index) => diagnostics.remove(index),
+            None => Diagnostic::default(),
+        };
+
+        self.diagnostics.append(&mut diagnostics);
+
+        last
     }
 
     fn find_module(
@@ -2399,7 +2933,7 @@ impl<'v> Visitor<'v> {
         root: &String,
         statement: &Statement,
         is_deep_run: bool,
-    ) -> Result<String, ()> {
+    ) -> Result<String, Diagnostic> {
         let is_deep_run = is_deep_run || self.is_deep;
 
         let my_folder = Path::new(&root);
@@ -2415,7 +2949,11 @@ impl<'v> Visitor<'v> {
         }
 
         let module = Path::new(&file_path);
-        let init_path = format!("{}/{}/init.wu", my_folder.to_str().unwrap(), path);
+        let mut init_path = format!("{}/{}/init.wu", my_folder.to_str().unwrap(), path);
+
+        if &init_path[0..1] == "/" && !is_deep_run {
+            init_path = format!(".{}", init_path)
+        }
 
         // if !is_deep_run {
         //     init_path = format!("./{}", init_path)
@@ -2483,11 +3021,17 @@ impl<'v> Visitor<'v> {
         kind: &Type,
         module_content: Option<&HashMap<String, Type>>,
         is_index: bool,
-    ) -> Result<(), ()> {
+    ) -> Result<(), Diagnostic> {
         let mut new_content = new_content.clone();
 
         let original_kind = kind.clone();
 
+        let generics = if let TypeNode::Struct(.., ref generics) = kind.node {
+            generics.clone()
+        } else {
+            Vec::new()
+        };
+
         for statement in ast.iter() {
             // don't visit function bodies
             
@@ -2521,7 +3065,12 @@ impl<'v> Visitor<'v> {
                         );
 
                         let kind = Type::new(
-                            TypeNode::Struct(struct_name.clone(), new_content.clone(), id.clone()),
+                            TypeNode::Struct(
+                                struct_name.clone(),
+                                new_content.clone(),
+                                id.clone(),
+                                generics.clone(),
+                            ),
                             kind.mode.clone(),
                         );
 
@@ -2582,6 +3131,7 @@ impl<'v> Visitor<'v> {
                                     struct_name.clone(),
                                     new_content.clone(),
                                     id.clone(),
+                                    generics.clone(),
                                 ),
                                 kind.mode.clone(),
                             );
@@ -2668,7 +3218,7 @@ impl<'v> Visitor<'v> {
         Ok(())
     }
 
-    fn ensure_no_implicit(&self, expression: &Expression) -> Result<(), ()> {
+    fn ensure_no_implicit(&self, expression: &Expression) -> Result<(), Diagnostic> {
         use self::ExpressionNode::*;
 
         match expression.node {
@@ -2685,6 +3235,12 @@ impl<'v> Visitor<'v> {
                                 self.ensure_no_implicit(&*expr)?
                             }
 
+                            Switch(_, ref arms) => {
+                                for arm in arms.iter() {
+                                    self.ensure_no_implicit(&arm.2)?
+                                }
+                            }
+
                             _ => {
                                 return Err(response!(
                                     Wrong("unexpected expression without context"),
@@ -2707,6 +3263,12 @@ impl<'v> Visitor<'v> {
                 self.ensure_no_implicit(&*expr)?
             }
 
+            Switch(_, ref arms) => {
+                for arm in arms.iter() {
+                    self.ensure_no_implicit(&arm.2)?
+                }
+            }
+
             _ => {
                 return Err(response!(
                     Wrong("unexpected expression without context"),
@@ -2719,7 +3281,571 @@ impl<'v> Visitor<'v> {
         Ok(())
     }
 
-    fn assert_types(&self, a: Type, b: Type, pos: &Pos) -> Result<bool, ()> {
+    // Checks a `switch` arm's pattern against what's switched on, binding the names in it.
+    // Alternatives can't bind anything, each would leave different names behind
+    fn visit_pattern(
+        &mut self,
+        pattern: &Pattern,
+        subject: &Type,
+        in_alternative: bool,
+    ) -> Result<(), Diagnostic> {
+        match pattern.node {
+            PatternNode::Wildcard => Ok(()),
+
+            PatternNode::Binding(ref name) => {
+                if let Some(payload) = Self::variant(subject, name) {
+                    if !payload.is_empty() {
+                        return Err(response!(
+                            Wrong(format!(
+                                "mismatched pattern, variant `{}` has {} field{}",
+                                name,
+                                payload.len(),
+                                if payload.len() > 1 { "s" } else { "" }
+                            )),
+                            self.source.file,
+                            pattern.pos
+                        ));
+                    }
+
+                    self.variant_patterns.insert(pattern.pos.clone(), true);
+
+                    return Ok(());
+                }
+
+                if in_alternative {
+                    return Err(response!(
+                        Wrong(format!("can't bind `{}` in an alternative", name)),
+                        self.source.file,
+                        pattern.pos,
+                        Note("use `_` to match anything without binding it")
+                    ));
+                }
+
+                self.assign(name.clone(), Type::from(subject.node.clone()));
+
+                Ok(())
+            }
+
+            PatternNode::Value(ref value) => {
+                self.visit_expression(value)?;
+
+                let value_type = self.type_expression(value)?;
+
+                if !subject
+                    .node
+                    .check_expression(&Parser::fold_expression(value).node)
+                    && value_type.node != subject.node
+                {
+                    return Err(response!(
+                        Wrong(format!(
+                            "mismatched types, expected `{}` got `{}`",
+                            subject, value_type
+                        )),
+                        self.source.file,
+                        pattern.pos
+                    ));
+                }
+
+                // a unit variant by its path, `Shape Empty`, matches by tag like the rest
+                if let ExpressionNode::Index(_, ref index, false) = value.node {
+                    if let ExpressionNode::Identifier(ref name) = index.node {
+                        if Self::variant(subject, name).is_some() {
+                            self.variant_patterns.insert(pattern.pos.clone(), true);
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+
+            PatternNode::Range(ref start, ref end) => {
+                match subject.node {
+                    TypeNode::Int | TypeNode::Float | TypeNode::Char | TypeNode::Any => (),
+
+                    _ => {
+                        return Err(response!(
+                            Wrong(format!("can't match range against `{}`", subject)),
+                            self.source.file,
+                            pattern.pos
+                        ))
+                    }
+                }
+
+                for bound in [start, end] {
+                    self.visit_expression(bound)?;
+
+                    let bound_type = self.type_expression(bound)?;
+
+                    if !subject
+                        .node
+                        .check_expression(&Parser::fold_expression(bound).node)
+                        && bound_type.node != subject.node
+                    {
+                        return Err(response!(
+                            Wrong(format!(
+                                "mismatched types, expected `{}` got `{}`",
+                                subject, bound_type
+                            )),
+                            self.source.file,
+                            bound.pos
+                        ));
+                    }
+                }
+
+                if let (Some(low), Some(high)) = (Self::constant(start), Self::constant(end)) {
+                    if low >= high {
+                        self.diagnostics.push(response!(
+                            Weird("empty range, it doesn't start below its end"),
+                            self.source.file,
+                            pattern.pos,
+                            Note("a range goes up to but not including its end")
+                        ))
+                    }
+                }
+
+                Ok(())
+            }
+
+            PatternNode::Tuple(ref content) => {
+                let kinds = match subject.node {
+                    TypeNode::Any => vec![Type::from(TypeNode::Any); content.len()],
+                    TypeNode::Tuple(ref kinds) => kinds.clone(),
+
+                    _ => {
+                        return Err(response!(
+                            Wrong(format!("can't match tuple against `{}`", subject)),
+                            self.source.file,
+                            pattern.pos
+                        ))
+                    }
+                };
+
+                if kinds.len() != content.len() {
+                    return Err(response!(
+                        Wrong(format!(
+                            "mismatched pattern, expected {} elements got {}",
+                            kinds.len(),
+                            content.len()
+                        )),
+                        self.source.file,
+                        pattern.pos
+                    ));
+                }
+
+                for (element, kind) in content.iter().zip(kinds.iter()) {
+                    self.visit_pattern(element, kind, in_alternative)?
+                }
+
+                Ok(())
+            }
+
+            PatternNode::Struct(ref path, ref fields) => {
+                self.visit_expression(path)?;
+
+                let struct_type = self.type_expression(path)?;
+
+                let content = match struct_type.node {
+                    TypeNode::Struct(_, ref content, ..)
+                        if struct_type.mode.strong_cmp(&TypeMode::Undeclared) =>
+                    {
+                        content.clone()
+                    }
+
+                    _ => {
+                        return Err(response!(
+                            Wrong(format!("can't match `{}` as a struct", struct_type.node)),
+                            self.source.file,
+                            path.pos
+                        ))
+                    }
+                };
+
+                if struct_type.node != subject.node {
+                    return Err(response!(
+                        Wrong(format!(
+                            "mismatched types, expected `{}` got `{}`",
+                            subject, struct_type.node
+                        )),
+                        self.source.file,
+                        pattern.pos
+                    ));
+                }
+
+                for (name, field) in fields.iter() {
+                    if let Some(kind) = content.get(name) {
+                        self.visit_pattern(field, kind, in_alternative)?
+                    } else {
+                        return Err(response!(
+                            Wrong(format!("no such struct member `{}`", name)),
+                            self.source.file,
+                            field.pos
+                        ));
+                    }
+                }
+
+                Ok(())
+            }
+
+            PatternNode::Variant(ref name, ref fields) => {
+                let payload = match subject.node {
+                    TypeNode::Any => vec![Type::from(TypeNode::Any); fields.len()],
+
+                    TypeNode::Enum(ref enum_name, ..) => match Self::variant(subject, name) {
+                        Some(payload) => payload,
+                        None => {
+                            return Err(response!(
+                                Wrong(format!(
+                                    "no such variant `{}` in enum `{}`",
+                                    name, enum_name
+                                )),
+                                self.source.file,
+                                pattern.pos
+                            ))
+                        }
+                    },
+
+                    _ => {
+                        return Err(response!(
+                            Wrong(format!(
+                                "can't match variant `{}` against `{}`",
+                                name, subject
+                            )),
+                            self.source.file,
+                            pattern.pos
+                        ))
+                    }
+                };
+
+                if payload.len() != fields.len() {
+                    return Err(response!(
+                        Wrong(format!(
+                            "mismatched pattern, variant `{}` has {} field{} got {}",
+                            name,
+                            payload.len(),
+                            if payload.len() != 1 { "s" } else { "" },
+                            fields.len()
+                        )),
+                        self.source.file,
+                        pattern.pos
+                    ));
+                }
+
+                for (field, kind) in fields.iter().zip(payload.iter()) {
+                    self.visit_pattern(field, kind, in_alternative)?
+                }
+
+                Ok(())
+            }
+
+            PatternNode::Alternative(ref alternatives) => {
+                for alternative in alternatives.iter() {
+                    self.visit_pattern(alternative, subject, true)?
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    // Whether a pattern matches anything it's given, once it's been visited
+    fn is_irrefutable(&self, pattern: &Pattern) -> bool {
+        match pattern.node {
+            PatternNode::Wildcard => true,
+            PatternNode::Binding(_) => !self.variant_patterns.contains_key(&pattern.pos),
+
+            PatternNode::Tuple(ref content) => content.iter().all(|p| self.is_irrefutable(p)),
+            PatternNode::Struct(_, ref fields) => {
+                fields.iter().all(|field| self.is_irrefutable(&field.1))
+            }
+            PatternNode::Alternative(ref alternatives) => {
+                alternatives.iter().any(|p| self.is_irrefutable(p))
+            }
+
+            _ => false,
+        }
+    }
+
+    // The variants a pattern matches no matter their payload
+    fn covered_variants(&self, pattern: &Pattern, subject: &Type) -> Vec<String> {
+        if self.is_irrefutable(pattern) {
+            if let TypeNode::Enum(_, ref variants, _) = subject.node {
+                return variants.iter().map(|variant| variant.0.clone()).collect();
+            }
+        }
+
+        match pattern.node {
+            PatternNode::Variant(ref name, ref fields) => {
+                if fields.iter().all(|field| self.is_irrefutable(field)) {
+                    vec![name.clone()]
+                } else {
+                    Vec::new()
+                }
+            }
+
+            PatternNode::Binding(ref name) => vec![name.clone()],
+
+            PatternNode::Value(ref value) => match value.node {
+                ExpressionNode::Index(_, ref index, false) => match index.node {
+                    ExpressionNode::Identifier(ref name)
+                        if Self::variant(subject, name).is_some() =>
+                    {
+                        vec![name.clone()]
+                    }
+
+                    _ => Vec::new(),
+                },
+
+                _ => Vec::new(),
+            },
+
+            PatternNode::Alternative(ref alternatives) => alternatives
+                .iter()
+                .flat_map(|alternative| self.covered_variants(alternative, subject))
+                .collect(),
+
+            _ => Vec::new(),
+        }
+    }
+
+    // A generic function's type at a call, its generics inferred from the arguments.
+    // Those the arguments say nothing about are left `any`
+    fn instantiate(&mut self, kind: Type, args: &[Expression]) -> Result<Type, Diagnostic> {
+        let mut generics = Vec::new();
+
+        kind.node.generics(&mut generics);
+
+        if generics.is_empty() {
+            return Ok(kind);
+        }
+
+        let mut bindings = HashMap::new();
+        let mut origins = HashMap::new();
+
+        if let TypeNode::Func(ref params, ..) = kind.node {
+            for (param, arg) in params.iter().zip(args.iter()) {
+                let param = self.deid(param.clone())?;
+                let arg_type = self.type_expression(arg)?;
+
+                param.node.infer(&arg_type.node, &mut bindings);
+
+                for name in bindings.keys() {
+                    if !origins.contains_key(name) {
+                        origins.insert(name.clone(), arg.pos.clone());
+                    }
+                }
+            }
+        }
+
+        for (name, bounds) in generics {
+            let bound = bindings
+                .entry(name.clone())
+                .or_insert_with(|| Type::from(TypeNode::Any))
+                .clone();
+
+            // left `any`, nothing to check
+            let position = match origins.get(&name) {
+                Some(position) => position.clone(),
+                None => continue,
+            };
+
+            self.check_bounds(&name, &bounds, &bound, position)?
+        }
+
+        Ok(Type::new(kind.node.substitute(&bindings), kind.mode))
+    }
+
+    fn check_bounds(
+        &mut self,
+        name: &str,
+        bounds: &[Type],
+        bound: &Type,
+        position: Pos,
+    ) -> Result<(), Diagnostic> {
+        for kind in self.bounds(bounds)? {
+            let (trait_name, content) = match kind.node {
+                TypeNode::Trait(name, content) => (name, content),
+                _ => continue,
+            };
+
+            let mut names = content.keys().cloned().collect::<Vec<String>>();
+
+            names.sort();
+
+            for method in names.iter() {
+                let expected = &content[method];
+
+                match self.implemented_method(bound, method) {
+                    Some(ref found) if found.node == expected.node => (),
+
+                    Some(found) => {
+                        return Err(response!(
+                            Wrong(format!(
+                                "expected implemented type `{}` for `{}`, `{}` has `{}`",
+                                expected, method, bound, found
+                            )),
+                            self.source.file,
+                            position,
+                            Note(format!("`{}` has to implement `{}`", name, trait_name))
+                        ))
+                    }
+
+                    None => {
+                        return Err(response!(
+                            Wrong(format!(
+                                "`{}` doesn't implement `{}`, missing method `{}`",
+                                bound, trait_name, method
+                            )),
+                            self.source.file,
+                            position,
+                            Note(format!("`{}` has to implement `{}`", name, trait_name))
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // The traits a generic is bounded by
+    fn bounds(&mut self, bounds: &[Type]) -> Result<Vec<Type>, Diagnostic> {
+        let mut traits = Vec::new();
+
+        for bound in bounds.iter() {
+            let kind = self.deid(bound.clone())?;
+
+            if let TypeNode::Trait(..) = kind.node {
+                traits.push(kind)
+            } else if let TypeNode::Id(ref expr) = bound.node {
+                return Err(response!(
+                    Wrong(format!("can't bound by `{}`, it's not a trait", kind)),
+                    self.source.file,
+                    expr.pos
+                ));
+            }
+        }
+
+        Ok(traits)
+    }
+
+    // A bounded generic seen as the traits it's bounded by, so their methods can be called
+    fn bounded(&mut self, kind: Type) -> Result<Type, Diagnostic> {
+        if let TypeNode::Generic(ref name, ref bounds) = kind.node {
+            if !bounds.is_empty() {
+                let mut content = HashMap::new();
+
+                for bound in self.bounds(bounds)? {
+                    if let TypeNode::Trait(_, methods) = bound.node {
+                        content.extend(methods)
+                    }
+                }
+
+                return Ok(Type::new(TypeNode::Trait(name.clone(), content), kind.mode.clone()));
+            }
+        }
+
+        Ok(kind)
+    }
+
+    // The type `kind` implements `method` with, `any` standing in for anything
+    fn implemented_method(&mut self, kind: &Type, method: &String) -> Option<Type> {
+        match kind.node {
+            TypeNode::Any => Some(kind.clone()),
+
+            TypeNode::Struct(_, ref content, ref id, _) => {
+                if self.is_implemented(id, method) {
+                    return Some(self.symtab.get_implementation_force(id, method));
+                }
+
+                content
+                    .get(method)
+                    .filter(|t| t.mode.strong_cmp(&TypeMode::Implemented))
+                    .cloned()
+            }
+
+            TypeNode::Generic(..) => match self.bounded(kind.clone()) {
+                Ok(Type {
+                    node: TypeNode::Trait(_, ref content),
+                    ..
+                }) => content.get(method).cloned(),
+
+                _ => None,
+            },
+
+            TypeNode::Trait(_, ref content) => content.get(method).cloned(),
+
+            _ => None,
+        }
+    }
+
+    // What initializing a generic struct makes, its type parameters inferred from the fields
+    fn instance(&mut self, kind: Type, args: &[(String, Expression)]) -> Result<Type, Diagnostic> {
+        let (content, generics) = match kind.node {
+            TypeNode::Struct(_, ref content, _, ref generics) if !generics.is_empty() => {
+                (content.clone(), generics.clone())
+            }
+
+            _ => return Ok(kind),
+        };
+
+        let mut bindings = HashMap::new();
+        let mut origins = HashMap::new();
+
+        for (name, arg) in args.iter() {
+            if let Some(field) = content.get(name) {
+                let arg_type = self.type_expression(arg)?;
+
+                field.node.infer(&arg_type.node, &mut bindings);
+
+                for name in bindings.keys() {
+                    if !origins.contains_key(name) {
+                        origins.insert(name.clone(), arg.pos.clone());
+                    }
+                }
+            }
+        }
+
+        for (name, generic) in generics {
+            let bound = bindings
+                .entry(name.clone())
+                .or_insert_with(|| Type::from(TypeNode::Any))
+                .clone();
+
+            if let (TypeNode::Generic(_, ref bounds), Some(position)) =
+                (&generic.node, origins.get(&name))
+            {
+                self.check_bounds(&name, bounds, &bound, position.clone())?
+            }
+        }
+
+        Ok(Type::new(kind.node.substitute(&bindings), kind.mode))
+    }
+
+    // The payload of the variant `name`, if `kind` is an enum with one
+    // The value of a number or character known before running anything
+    fn constant(expression: &Expression) -> Option<f64> {
+        match Parser::fold_expression(expression).node {
+            ExpressionNode::Int(n) => Some(n as f64),
+            ExpressionNode::Float(n) => Some(n),
+            ExpressionNode::Char(c) => Some(c as u32 as f64),
+            ExpressionNode::Neg(ref inner) => Self::constant(inner).map(|n| -n),
+
+            _ => None,
+        }
+    }
+
+    fn variant(kind: &Type, name: &str) -> Option<Vec<Type>> {
+        if let TypeNode::Enum(_, ref variants, _) = kind.node {
+            variants
+                .iter()
+                .find(|variant| variant.0 == name)
+                .map(|variant| variant.1.clone())
+        } else {
+            None
+        }
+    }
+
+    fn assert_types(&self, a: Type, b: Type, pos: &Pos) -> Result<bool, Diagnostic> {
         if a != b {
             Err(response!(
                 Wrong(format!("mismatched types, expected `{}` got `{}`", a, b)),
@@ -2731,8 +3857,12 @@ impl<'v> Visitor<'v> {
         }
     }
 
-    fn fetch(&self, name: &String, pos: &Pos) -> Result<Type, ()> {
+    fn fetch(&mut self, name: &String, pos: &Pos) -> Result<Type, Diagnostic> {
         if let Some(t) = self.symtab.fetch(name) {
+            if self.symtab.is_poisoned(name) {
+                self.poisoned.push(pos.clone())
+            }
+
             Ok(t)
         } else {
             Err(response!(
@@ -2759,7 +3889,47 @@ impl<'v> Visitor<'v> {
         self.symtab.pop()
     }
 
-    pub fn deid(&mut self, t: Type) -> Result<Type, ()> {
+    pub fn deid(&mut self, t: Type) -> Result<Type, Diagnostic> {
+        if let TypeNode::Applied(ref expr, ref args) = t.node {
+            let kind = self.deid(Type::new(TypeNode::Id(expr.clone()), t.mode.clone()))?;
+
+            if let TypeNode::Struct(ref name, _, _, ref generics) = kind.node {
+                if generics.len() != args.len() {
+                    return Err(response!(
+                        Wrong(format!(
+                            "`{}` takes {} type argument{}, got {}",
+                            name,
+                            generics.len(),
+                            if generics.len() != 1 { "s" } else { "" },
+                            args.len()
+                        )),
+                        self.source.file,
+                        expr.pos
+                    ));
+                }
+
+                let mut bindings = HashMap::new();
+
+                for (generic, arg) in generics.iter().zip(args.iter()) {
+                    let arg = self.deid(arg.clone())?;
+
+                    if let TypeNode::Generic(_, ref bounds) = generic.1.node {
+                        self.check_bounds(&generic.0, bounds, &arg, expr.pos.clone())?
+                    }
+
+                    bindings.insert(generic.0.clone(), arg);
+                }
+
+                return Ok(Type::new(kind.node.substitute(&bindings), kind.mode));
+            }
+
+            return Err(response!(
+                Wrong(format!("can't give type arguments to `{}`", kind.node)),
+                self.source.file,
+                expr.pos
+            ));
+        }
+
         if let TypeNode::Optional(ref content) = t.node {
             return Ok(Type::new(
                 TypeNode::Optional(Rc::new(self.deid(Type::from((**content).clone()))?.node)),